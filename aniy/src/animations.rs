@@ -13,8 +13,23 @@ use crate::{
 /// The `Animation` trait is implemented by all animations.
 pub trait Animation: Send + Sync {
     /// Given a progress value between 0.0 and 1.0, returns the z-index and the SVG node.
+    ///
+    /// Easing curves can overshoot, so the progress may go slightly outside that range,
+    /// animations that can not go past their ends should clamp it.
     fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>);
 
+    /// Like `animate`, but also given the time in seconds since the start of the animation.
+    ///
+    /// Animations that depend on the elapsed time rather than the progress should override this,
+    /// by default the time is ignored.
+    fn animate_at(
+        &self,
+        progress: f32,
+        _time: f32,
+    ) -> (isize, Box<dyn svg::Node>) {
+        self.animate(progress)
+    }
+
     /// Create a new `AnimationContainer` with the given animation.
     fn container(self) -> AnimationContainer
     where
@@ -33,6 +48,12 @@ pub struct AnimationContainer {
     pub start: f32,
     /// The end time of the animation in seconds.
     pub end: f32,
+    /// An optional name for the animation, used for chapter markers.
+    pub label: Option<String>,
+    /// The easing curve applied to the progress.
+    ///
+    /// Gets the linear progress between 0.0 and 1.0, and may return values outside that range.
+    pub easing: Arc<dyn Fn(f32) -> f32 + Send + Sync>,
 }
 
 impl AnimationContainer {
@@ -44,6 +65,8 @@ impl AnimationContainer {
             animation,
             start: 0.0,
             end: 1.0,
+            label: None,
+            easing: Arc::new(|progress| progress),
         }
     }
 
@@ -52,10 +75,15 @@ impl AnimationContainer {
         &self,
         time: f32,
     ) -> (isize, Box<dyn svg::Node>) {
-        let progress = (time - self.start) / (self.end - self.start);
-        let progress = progress.clamp(0.0, 1.0);
-
-        self.animation.animate(progress)
+        // A zero (or negative) duration would divide by zero, so it counts as already done.
+        let progress = if self.end <= self.start {
+            1.0
+        } else {
+            (time - self.start) / (self.end - self.start)
+        };
+        let progress = (self.easing)(progress.clamp(0.0, 1.0));
+
+        self.animation.animate_at(progress, time - self.start)
     }
 
     /// Set the end time as to make the duration of the animation the given duration.
@@ -70,6 +98,39 @@ impl AnimationContainer {
         self
     }
 
+    /// Multiply the duration of the animation by the given factor, keeping the start time.
+    pub fn scaled_duration(mut self, factor: f32) -> Self {
+        self.end = self.start + (self.end - self.start) * factor;
+        self
+    }
+
+    /// Set the easing curve of the animation.
+    ///
+    /// Defaults to `Easing::Linear`.
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing =
+            Arc::new(move |progress| easing.apply(progress));
+        self
+    }
+
+    /// Set a custom easing curve for the animation.
+    ///
+    /// The function gets the linear progress between 0.0 and 1.0,
+    /// and may return values outside that range for overshoot effects.
+    pub fn easing_fn(
+        mut self,
+        easing: impl Fn(f32) -> f32 + Send + Sync + 'static,
+    ) -> Self {
+        self.easing = Arc::new(easing);
+        self
+    }
+
+    /// Name the animation, labeled animations are used as chapter markers.
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
     /// Shift the start and end time by the given delay.
     pub fn delay(mut self, delay: f32) -> Self {
         self.start += delay;
@@ -105,6 +166,15 @@ impl AnimationContainer {
         self
     }
 
+    /// Trim the start and end time to lie within the given animation.
+    ///
+    /// Useful to make sure a nested animation never runs past its parent.
+    pub fn clamp_to(mut self, parent: &AnimationContainer) -> Self {
+        self.start = self.start.clamp(parent.start, parent.end);
+        self.end = self.end.clamp(parent.start, parent.end);
+        self
+    }
+
     /// Reverse the animation.
     pub fn reverse(self) -> Self {
         Self {
@@ -113,6 +183,128 @@ impl AnimationContainer {
             }),
             start: self.start,
             end: self.end,
+            label: self.label,
+            easing: self.easing,
+        }
+    }
+
+    /// Play the animation `times` times within the current duration.
+    ///
+    /// With 2 repeats the animation is back at its start halfway through.
+    /// The easing is applied to the whole container, not to each repeat.
+    pub fn repeat(self, times: u32) -> Self {
+        Self {
+            animation: Arc::new(RepeatAnimation {
+                animation: self.animation,
+                times,
+            }),
+            ..self
+        }
+    }
+
+    /// Keep looping the animation, each loop lasting the current duration.
+    ///
+    /// Set the duration afterwards to how long it should keep looping,
+    /// for example with `duration` or `end_with`.
+    pub fn repeat_forever(self) -> Self {
+        Self {
+            animation: Arc::new(LoopAnimation {
+                animation: self.animation,
+                period: self.end - self.start,
+            }),
+            ..self
+        }
+    }
+
+    /// Play the animation forward and then backward within the current duration.
+    ///
+    /// Combine with `repeat` or `repeat_forever` to ping-pong several times.
+    pub fn yoyo(self) -> Self {
+        Self {
+            animation: Arc::new(YoyoAnimation {
+                animation: self.animation,
+            }),
+            ..self
+        }
+    }
+
+    /// Turn an enter animation into an exit animation lasting `duration` seconds.
+    ///
+    /// Shorthand for `.reverse().duration(duration)`.
+    pub fn as_exit(self, duration: f32) -> Self {
+        self.reverse().duration(duration)
+    }
+
+    /// Create a reversed copy of the animation, with the same timing.
+    ///
+    /// Useful for creating an exit animation from an enter animation.
+    pub fn mirror(&self) -> Self {
+        self.clone().reverse()
+    }
+}
+
+/// An easing curve, mapping the linear progress of an animation to the progress it is drawn at.
+///
+/// See <https://easings.net> for what each curve looks like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    /// Constant speed.
+    #[default]
+    Linear,
+    /// Starts slow and speeds up.
+    EaseInQuad,
+    /// Starts fast and slows down.
+    EaseOutQuad,
+    /// Slow at both ends.
+    EaseInOutQuad,
+    /// Like `EaseInQuad`, but more pronounced.
+    EaseInCubic,
+    /// Like `EaseOutQuad`, but more pronounced.
+    EaseOutCubic,
+    /// Like `EaseInOutQuad`, but more pronounced.
+    EaseInOutCubic,
+    /// A gentle curve that is slow at both ends.
+    EaseInOutSine,
+    /// Overshoots the end slightly before settling.
+    ///
+    /// The progress goes above 1.0 for a moment,
+    /// animations that can not overshoot like `PolygonDraw` simply stop at the end.
+    EaseOutBack,
+}
+
+impl Easing {
+    /// Apply the curve to a progress between 0.0 and 1.0.
+    pub fn apply(self, t: f32) -> f32 {
+        /// How far `EaseOutBack` overshoots.
+        const BACK: f32 = 1.70158;
+
+        match self {
+            Self::Linear => t,
+            Self::EaseInQuad => t * t,
+            Self::EaseOutQuad => 1.0 - (1.0 - t) * (1.0 - t),
+            Self::EaseInOutQuad => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Self::EaseInCubic => t * t * t,
+            Self::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+            Self::EaseInOutCubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Self::EaseInOutSine => {
+                -((std::f32::consts::PI * t).cos() - 1.0) / 2.0
+            }
+            Self::EaseOutBack => {
+                1.0 + (BACK + 1.0) * (t - 1.0).powi(3)
+                    + BACK * (t - 1.0).powi(2)
+            }
         }
     }
 }
@@ -146,6 +338,46 @@ impl AnimatedObject {
         self.enter = self.enter.after(&other.exit);
         self.lifetime(current_lifetime)
     }
+
+    /// Delay both the enter and exit animation by the given amount of seconds.
+    pub fn delay(mut self, delay: f32) -> Self {
+        self.enter = self.enter.delay(delay);
+        self.exit = self.exit.delay(delay);
+        self
+    }
+}
+
+/// Something that can be moved later in time, used by `stagger`.
+pub trait Delay {
+    /// Delay by the given amount of seconds.
+    fn delay(self, delay: f32) -> Self;
+}
+
+impl Delay for AnimationContainer {
+    fn delay(self, delay: f32) -> Self {
+        AnimationContainer::delay(self, delay)
+    }
+}
+
+impl Delay for AnimatedObject {
+    fn delay(self, delay: f32) -> Self {
+        AnimatedObject::delay(self, delay)
+    }
+}
+
+/// Delay each item by `step` seconds more than the previous one, for a cascade effect.
+///
+/// The first item is left as is, the second is delayed by `step`, the third by `2 * step`, and so on.
+/// Works on both `AnimationContainer`s and `AnimatedObject`s.
+pub fn stagger<T: Delay>(
+    items: impl IntoIterator<Item = T>,
+    step: f32,
+) -> Vec<T> {
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| item.delay(index as f32 * step))
+        .collect()
 }
 
 /// An animation that does nothing.
@@ -173,6 +405,255 @@ impl Animation for ReverseAnimation {
     fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
         self.animation.animate(1.0 - progress)
     }
+
+    fn animate_at(
+        &self,
+        progress: f32,
+        time: f32,
+    ) -> (isize, Box<dyn svg::Node>) {
+        self.animation.animate_at(1.0 - progress, time)
+    }
+}
+
+/// An animation that plays the given animation several times in a row.
+pub struct RepeatAnimation {
+    /// The animation to repeat.
+    pub animation: Arc<dyn Animation>,
+    /// How many times to play the animation.
+    pub times: u32,
+}
+
+impl RepeatAnimation {
+    /// The progress of the current repeat.
+    fn cycle(&self, progress: f32) -> f32 {
+        if progress >= 1.0 {
+            return 1.0;
+        }
+        (progress * self.times as f32).rem_euclid(1.0)
+    }
+}
+
+impl Animation for RepeatAnimation {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        self.animation.animate(self.cycle(progress))
+    }
+
+    fn animate_at(
+        &self,
+        progress: f32,
+        time: f32,
+    ) -> (isize, Box<dyn svg::Node>) {
+        self.animation.animate_at(self.cycle(progress), time)
+    }
+}
+
+/// An animation that loops the given animation based on the elapsed time.
+pub struct LoopAnimation {
+    /// The animation to loop.
+    pub animation: Arc<dyn Animation>,
+    /// How long each loop takes in seconds.
+    pub period: f32,
+}
+
+impl Animation for LoopAnimation {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        self.animation.animate(progress)
+    }
+
+    fn animate_at(
+        &self,
+        _progress: f32,
+        time: f32,
+    ) -> (isize, Box<dyn svg::Node>) {
+        let progress = if self.period > 0.0 {
+            (time / self.period).rem_euclid(1.0)
+        } else {
+            1.0
+        };
+        self.animation.animate_at(progress, time)
+    }
+}
+
+/// An animation that plays the given animation forward and then backward.
+pub struct YoyoAnimation {
+    /// The animation to play back and forth.
+    pub animation: Arc<dyn Animation>,
+}
+
+impl Animation for YoyoAnimation {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        self.animation.animate(1.0 - (2.0 * progress - 1.0).abs())
+    }
+
+    fn animate_at(
+        &self,
+        progress: f32,
+        time: f32,
+    ) -> (isize, Box<dyn svg::Node>) {
+        self.animation
+            .animate_at(1.0 - (2.0 * progress - 1.0).abs(), time)
+    }
+}
+
+/// Plays several animations one after another, within a single container.
+///
+/// Only the active animation is drawn.
+pub struct Sequence {
+    /// The animations to play in order.
+    pub animations: Vec<Arc<dyn Animation>>,
+    /// The share of the duration each animation gets, relative to the others.
+    pub weights: Vec<f32>,
+}
+
+impl Sequence {
+    /// Create a sequence where each animation gets an equal share of the duration.
+    pub fn new(animations: Vec<Arc<dyn Animation>>) -> Self {
+        let weights = vec![1.0; animations.len()];
+        Self {
+            animations,
+            weights,
+        }
+    }
+
+    /// Create a sequence where each animation gets a share of the duration relative to the others.
+    ///
+    /// Weights of `1.0` and `2.0` make the second animation last twice as long as the first.
+    pub fn weighted(
+        animations: Vec<(Arc<dyn Animation>, f32)>,
+    ) -> Self {
+        let (animations, weights) = animations.into_iter().unzip();
+        Self {
+            animations,
+            weights,
+        }
+    }
+}
+
+impl Sequence {
+    /// The animation active at the given progress, and its own progress.
+    fn active(&self, progress: f32) -> Option<(&dyn Animation, f32)> {
+        let last = self.animations.last()?;
+
+        let total = self.weights.iter().sum::<f32>();
+        let target = progress * total;
+        let mut start = 0.0;
+        for (animation, weight) in
+            self.animations.iter().zip(&self.weights)
+        {
+            let end = start + weight;
+            if target < end {
+                let local = (target - start) / weight;
+                return Some((animation.as_ref(), local.max(0.0)));
+            }
+            start = end;
+        }
+
+        // Only reached at (or past) the end, so the last animation finishes exactly.
+        Some((last.as_ref(), 1.0))
+    }
+}
+
+impl Animation for Sequence {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        match self.active(progress) {
+            Some((animation, progress)) => {
+                animation.animate(progress)
+            }
+            None => (0, Box::new(svg::node::element::Group::new())),
+        }
+    }
+
+    fn animate_at(
+        &self,
+        progress: f32,
+        time: f32,
+    ) -> (isize, Box<dyn svg::Node>) {
+        match self.active(progress) {
+            Some((animation, progress)) => {
+                animation.animate_at(progress, time)
+            }
+            None => (0, Box::new(svg::node::element::Group::new())),
+        }
+    }
+}
+
+/// Plays several animations at the same time, drawn as a single group.
+///
+/// Inside the group the animations are stacked by their own z-index.
+pub struct Parallel {
+    /// The animations to play.
+    pub animations: Vec<Arc<dyn Animation>>,
+    /// Which z-index of the animations the group uses.
+    pub z_index: ParallelZ,
+}
+
+/// Which z-index of its animations a `Parallel` group uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParallelZ {
+    /// The highest z-index, so the group is drawn above everything its parts would be.
+    #[default]
+    Max,
+    /// The lowest z-index, so the group is drawn below everything its parts would be.
+    Min,
+}
+
+impl Parallel {
+    /// Create a new `Parallel` from the given animations, using the highest z-index.
+    pub fn new(animations: Vec<Arc<dyn Animation>>) -> Self {
+        Self {
+            animations,
+            z_index: ParallelZ::Max,
+        }
+    }
+
+    /// Set which z-index of the animations the group uses.
+    pub fn z_index(mut self, z_index: ParallelZ) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    /// Group the rendered animations together.
+    fn combine(
+        &self,
+        mut nodes: Vec<(isize, Box<dyn svg::Node>)>,
+    ) -> (isize, Box<dyn svg::Node>) {
+        nodes.sort_by_key(|(z, _)| *z);
+        let z = match self.z_index {
+            ParallelZ::Max => nodes.last(),
+            ParallelZ::Min => nodes.first(),
+        }
+        .map_or(0, |(z, _)| *z);
+
+        let mut group = svg::node::element::Group::new();
+        for (_, node) in nodes {
+            group = group.add(node);
+        }
+        (z, Box::new(group))
+    }
+}
+
+impl Animation for Parallel {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        self.combine(
+            self.animations
+                .iter()
+                .map(|animation| animation.animate(progress))
+                .collect(),
+        )
+    }
+
+    fn animate_at(
+        &self,
+        progress: f32,
+        time: f32,
+    ) -> (isize, Box<dyn svg::Node>) {
+        self.combine(
+            self.animations
+                .iter()
+                .map(|animation| animation.animate_at(progress, time))
+                .collect(),
+        )
+    }
 }
 
 /// An animation that fades in the given object.
@@ -199,49 +680,487 @@ impl Animation for FadeAnimation {
     }
 }
 
+/// An animation that fades out the given object.
+///
+/// The same as a reversed `FadeAnimation`, meant to be used as an exit animation.
+pub struct FadeOut(FadeAnimation);
+
+impl FadeOut {
+    /// Create a new `FadeOut` from the given object.
+    /// By pre-rendering the object.
+    pub fn new(object: &impl Object) -> Self {
+        Self(FadeAnimation::new(object))
+    }
+}
+
+impl Animation for FadeOut {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        self.0.animate(1.0 - progress)
+    }
+}
+
+/// An animation that moves the given object by an offset.
+///
+/// Works on any object, `from` and `to` are offsets from where the object is placed,
+/// so `(0.0, 0.0)` is its own position.
+/// Use the container's easing to move with a curve.
+pub struct Move {
+    /// The z-index of the object.
+    z_index: isize,
+    /// The pre-rendered object.
+    node: Box<dyn svg::Node>,
+    /// The offset at the start of the animation.
+    from: (f32, f32),
+    /// The offset at the end of the animation.
+    to: (f32, f32),
+}
+
+impl Move {
+    /// Create a new `Move` from the given object, by pre-rendering the object.
+    pub fn new(
+        object: &impl Object,
+        from: (f32, f32),
+        to: (f32, f32),
+    ) -> Self {
+        let (z_index, node) = object.render();
+        Self {
+            z_index,
+            node,
+            from,
+            to,
+        }
+    }
+}
+
+impl Animation for Move {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        let x = self.from.0 + (self.to.0 - self.from.0) * progress;
+        let y = self.from.1 + (self.to.1 - self.from.1) * progress;
+        let group = svg::node::element::Group::new()
+            .set("transform", format!("translate({x}, {y})"))
+            .add(self.node.clone());
+
+        (self.z_index, Box::new(group))
+    }
+}
+
+/// An animation that scales the given object about the center of its bounding box.
+///
+/// Works on any object.
+pub struct Scale {
+    /// The z-index of the object.
+    z_index: isize,
+    /// The pre-rendered object.
+    node: Box<dyn svg::Node>,
+    /// The center of the object's bounding box.
+    pivot: (f32, f32),
+    /// The scale at the start of the animation.
+    from: f32,
+    /// The scale at the end of the animation.
+    to: f32,
+}
+
+impl Scale {
+    /// Create a new `Scale` from the given object, by pre-rendering the object.
+    pub fn new(object: &impl Object, from: f32, to: f32) -> Self {
+        let (z_index, node) = object.render();
+        Self {
+            z_index,
+            node,
+            pivot: bounding_box_center(object),
+            from,
+            to,
+        }
+    }
+}
+
+impl Animation for Scale {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        let scale = self.from + (self.to - self.from) * progress;
+        let (cx, cy) = self.pivot;
+        let group = svg::node::element::Group::new()
+            .set(
+                "transform",
+                format!(
+                    "translate({cx}, {cy}) scale({scale}) translate({}, {})",
+                    -cx, -cy
+                ),
+            )
+            .add(self.node.clone());
+
+        (self.z_index, Box::new(group))
+    }
+}
+
+/// An animation that rotates the given object about the center of its bounding box.
+///
+/// Works on any object, the angles are in degrees clockwise.
+pub struct Rotate {
+    /// The z-index of the object.
+    z_index: isize,
+    /// The pre-rendered object.
+    node: Box<dyn svg::Node>,
+    /// The center of the object's bounding box.
+    pivot: (f32, f32),
+    /// The angle at the start of the animation.
+    from: f32,
+    /// The angle at the end of the animation.
+    to: f32,
+}
+
+impl Rotate {
+    /// Create a new `Rotate` from the given object, by pre-rendering the object.
+    pub fn new(object: &impl Object, from: f32, to: f32) -> Self {
+        let (z_index, node) = object.render();
+        Self {
+            z_index,
+            node,
+            pivot: bounding_box_center(object),
+            from,
+            to,
+        }
+    }
+}
+
+impl Animation for Rotate {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        let angle = self.from + (self.to - self.from) * progress;
+        let (cx, cy) = self.pivot;
+        let group = svg::node::element::Group::new()
+            .set("transform", format!("rotate({angle}, {cx}, {cy})"))
+            .add(self.node.clone());
+
+        (self.z_index, Box::new(group))
+    }
+}
+
+/// The center of the object's bounding box.
+fn bounding_box_center(object: &impl Object) -> Point {
+    let bounds = object.bounding_box();
+    (
+        bounds.x() + bounds.width() / 2.0,
+        bounds.y() + bounds.height() / 2.0,
+    )
+}
+
+/// Makes the opacity of the given object oscillate smoothly between two values.
+///
+/// The oscillation is driven by the elapsed time, not the progress,
+/// so the container can be given any duration and the object will pulse for all of it.
+/// To keep an object pulsing while it is present, use this as the enter animation with the lifetime as duration.
+pub struct Pulse {
+    /// The object to pulse.
+    pub object: Arc<dyn Object>,
+    /// The lowest opacity reached.
+    pub min_opacity: f32,
+    /// The highest opacity reached.
+    pub max_opacity: f32,
+    /// The amount of full oscillations per second.
+    pub frequency: f32,
+}
+
+impl Pulse {
+    /// The opacity at the given time, starting at `min_opacity`.
+    pub fn opacity(&self, time: f32) -> f32 {
+        let phase = time * self.frequency * std::f32::consts::TAU;
+        let amount = (1.0 - phase.cos()) / 2.0;
+        self.min_opacity
+            + (self.max_opacity - self.min_opacity) * amount
+    }
+}
+
+impl Animation for Pulse {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        self.animate_at(progress, 0.0)
+    }
+
+    fn animate_at(
+        &self,
+        _progress: f32,
+        time: f32,
+    ) -> (isize, Box<dyn svg::Node>) {
+        let (z, node) = self.object.render();
+        let group = svg::node::element::Group::new()
+            .add(node)
+            .set("opacity", self.opacity(time));
+
+        (z, Box::new(group))
+    }
+}
+
+/// Inverts the colors of the given object and back again, like a camera flash.
+///
+/// The inversion peaks halfway through, and the object is back to normal at the end.
+/// Works on any object.
+pub struct InvertFlash(isize, Box<dyn svg::Node>);
+
+impl InvertFlash {
+    /// Create a new `InvertFlash` from the given object.
+    /// By pre-rendering the object.
+    pub fn new(object: &impl Object) -> Self {
+        let (z, node) = object.render();
+        Self(z, node)
+    }
+}
+
+impl Animation for InvertFlash {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        let amount = 1.0 - (2.0 * progress - 1.0).abs();
+        if amount <= 0.0 {
+            return (self.0, self.1.clone());
+        }
+
+        // Every channel becomes `channel * (1 - 2 * amount) + amount`,
+        // which is `1 - channel` at full strength.
+        let scale = 1.0 - 2.0 * amount;
+        let id = crate::unique_id("invert");
+        let filter = svg::node::element::Filter::new()
+            .set("id", id.as_str())
+            .set("color-interpolation-filters", "sRGB")
+            .add(
+                svg::node::element::FilterEffectColorMatrix::new()
+                    .set("type", "matrix")
+                    .set(
+                        "values",
+                        format!(
+                            "{scale} 0 0 0 {amount} \
+                             0 {scale} 0 0 {amount} \
+                             0 0 {scale} 0 {amount} \
+                             0 0 0 1 0"
+                        ),
+                    ),
+            );
+        let group = svg::node::element::Group::new()
+            .set("filter", format!("url(#{id})"))
+            .add(self.1.clone());
+
+        let node = svg::node::element::Group::new()
+            .add(svg::node::element::Definitions::new().add(filter))
+            .add(group);
+        (self.0, Box::new(node))
+    }
+}
+
 /// An animation that draws in a polygon from the first point to the last.
 pub struct PolygonDraw(pub Arc<objects::Polygon>);
 
 impl Animation for PolygonDraw {
     fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
-        let mut polygon = (*self.0).clone();
+        let polygon = (*self.0).clone();
+        let count = polygon.points.len();
+        if progress >= 1.0 || count < 2 {
+            return polygon.render();
+        }
 
-        let done_amount =
-            (polygon.points.len() as f32 * progress).floor() as usize;
-        if done_amount == polygon.points.len() {
+        // There are as many edges as points, the last one closes the polygon.
+        // Every edge, including the closing one, gets an equal slice of the progress.
+        let position = progress.max(0.0) * count as f32;
+        let edge = (position.floor() as usize).min(count - 1);
+        let edge_progress = position - edge as f32;
+
+        let start = polygon.points[edge];
+        let end = polygon.points[(edge + 1) % count];
+        let mut points = polygon.points[..=edge].to_vec();
+        points.push((
+            start.0 + (end.0 - start.0) * edge_progress,
+            start.1 + (end.1 - start.1) * edge_progress,
+        ));
+
+        draw_partial_polygon(polygon, points)
+    }
+}
+
+/// Like `PolygonDraw`, but the pen moves at a constant speed.
+///
+/// `PolygonDraw` spends the same time on every edge,
+/// this advances along the outline by length so long edges take longer to draw than short ones.
+pub struct PolygonTrace(pub Arc<objects::Polygon>);
+
+impl Animation for PolygonTrace {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        let polygon = (*self.0).clone();
+        let count = polygon.points.len();
+        if progress >= 1.0 || count < 2 {
             return polygon.render();
         }
 
-        let mut points = Vec::with_capacity(done_amount);
+        let edge = |i: usize| {
+            (polygon.points[i], polygon.points[(i + 1) % count])
+        };
+        let perimeter = (0..count)
+            .map(|i| {
+                let (start, end) = edge(i);
+                distance(start, end)
+            })
+            .sum::<f32>();
+
+        let mut remaining = perimeter * progress;
+        let mut points = vec![polygon.points[0]];
+        for i in 0..count {
+            let (start, end) = edge(i);
+            let length = distance(start, end);
+            if remaining < length {
+                let edge_progress = remaining / length;
+                points.push((
+                    start.0 + (end.0 - start.0) * edge_progress,
+                    start.1 + (end.1 - start.1) * edge_progress,
+                ));
+                break;
+            }
+            remaining -= length;
+            points.push(end);
+        }
+
+        draw_partial_polygon(polygon, points)
+    }
+}
 
-        for point in &polygon.points[..done_amount + 1] {
-            points.push(*point);
+/// Render the part of the polygon drawn so far.
+///
+/// The polygon is filled in using the given points, and the outline is drawn along them without closing it.
+fn draw_partial_polygon(
+    mut polygon: objects::Polygon,
+    points: Vec<Point>,
+) -> (isize, Box<dyn svg::Node>) {
+    polygon.points.clone_from(&points);
+    let outline_color = polygon.outline_color;
+    polygon.outline_color = Color(0, 0, 0, 0);
+    let (z, polygon_render) = polygon.render();
+
+    let mut line = svg::node::element::Polyline::new()
+        .set(
+            "points",
+            crate::format_points(&points, polygon.precision),
+        )
+        .set("fill", "none")
+        .set("stroke-width", polygon.stroke_width);
+    line = line.set("stroke", outline_color.as_css().as_ref());
+
+    let group = svg::node::element::Group::new()
+        .add(polygon_render)
+        .add(line);
+    (z, Box::new(group))
+}
+
+/// An animation that draws a line from its start to its end.
+pub struct DrawLine(pub Arc<objects::Line>);
+
+impl Animation for DrawLine {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        self.0.render_partial(progress)
+    }
+}
+
+/// An animation that grows an arrow from its start to its tip.
+///
+/// The head appears at the end, once the line reaches it.
+pub struct GrowArrow(pub Arc<objects::Arrow>);
+
+impl Animation for GrowArrow {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        self.0.render_partial(progress)
+    }
+}
+
+/// An animation that draws a line under a bounding box from left to right.
+///
+/// Use with `Math::part_bounding_box` to underline a term of an equation.
+pub struct DrawUnderline {
+    /// The x position of the left end.
+    pub left: f32,
+    /// The x position of the right end.
+    pub right: f32,
+    /// The y position of the line.
+    pub y: f32,
+    /// The color of the line.
+    pub color: Color,
+    /// The stroke width of the line.
+    pub stroke_width: f32,
+    /// The z-index of the line.
+    pub z_index: isize,
+}
+
+impl DrawUnderline {
+    /// Create a line spanning the width of the bounding box, just below it.
+    pub fn new(bounds: resvg::usvg::Rect) -> Self {
+        Self {
+            left: bounds.left(),
+            right: bounds.right(),
+            y: bounds.bottom() + objects::UNDER_GAP,
+            color: Color::rgb(255, 255, 255),
+            stroke_width: 5.0,
+            z_index: 0,
         }
+    }
+
+    /// Sets the color of the line.
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
 
-        let start = polygon.points[done_amount];
-        let end =
-            polygon.points[(done_amount + 1) % polygon.points.len()];
+    /// Sets the stroke width of the line.
+    pub fn stroke_width(mut self, stroke_width: f32) -> Self {
+        self.stroke_width = stroke_width;
+        self
+    }
 
-        let segment_progress = progress * polygon.points.len() as f32
-            - done_amount as f32;
-        let x = start.0 + (end.0 - start.0) * segment_progress;
-        let y = start.1 + (end.1 - start.1) * segment_progress;
+    /// Sets the z-index of the line.
+    pub fn z_index(mut self, z_index: isize) -> Self {
+        self.z_index = z_index;
+        self
+    }
+}
 
-        points.push((x, y));
-        polygon.points.clone_from(&points);
-        let outline_color = polygon.outline_color;
-        polygon.outline_color = Color(0, 0, 0, 0);
-        let (z, polygon_render) = polygon.render();
+impl Animation for DrawUnderline {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        let end = self.left
+            + (self.right - self.left) * progress.clamp(0.0, 1.0);
+        let line = svg::node::element::Line::new()
+            .set("x1", self.left)
+            .set("y1", self.y)
+            .set("x2", end)
+            .set("y2", self.y)
+            .set("stroke", self.color.as_css().as_ref())
+            .set("stroke-width", self.stroke_width);
+
+        (self.z_index, Box::new(line))
+    }
+}
 
-        let mut line = svg::node::element::Polyline::new()
-            .set("points", points)
-            .set("fill", "none")
-            .set("stroke-width", polygon.stroke_width);
-        line = line.set("stroke", outline_color.as_css().as_ref());
+/// An animation that reveals a brace from left to right.
+pub struct DrawBrace(pub Arc<objects::Brace>);
 
+impl Animation for DrawBrace {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        let brace = &self.0;
+        let (z, node) = brace.render();
+
+        // Clip to everything left of the drawn part, with room for the stroke at the ends.
+        let margin = brace.stroke_width;
+        let left = brace.left.min(brace.right) - margin;
+        let width = ((brace.right - brace.left).abs() + margin * 2.0)
+            * progress.clamp(0.0, 1.0);
+        let top = brace.y.min(brace.y + brace.depth) - margin;
+        let height = brace.depth.abs() + margin * 2.0;
+
+        let id = crate::unique_id("reveal");
+        let clip = svg::node::element::ClipPath::new()
+            .set("id", id.as_str())
+            .add(
+                svg::node::element::Rectangle::new()
+                    .set("x", left)
+                    .set("y", top)
+                    .set("width", width)
+                    .set("height", height),
+            );
         let group = svg::node::element::Group::new()
-            .add(polygon_render)
-            .add(line);
+            .add(svg::node::element::Definitions::new().add(clip))
+            .add(
+                svg::node::element::Group::new()
+                    .set("clip-path", format!("url(#{id})"))
+                    .add(node),
+            );
         (z, Box::new(group))
     }
 }
@@ -256,6 +1175,8 @@ pub struct PolygonMorph {
     start_points: Vec<(f32, f32)>,
     /// The ending points of the polygon potentially with missing points inserted.
     end_points: Vec<(f32, f32)>,
+    /// Whether to interpolate the colors in Oklab rather than sRGB.
+    perceptual: bool,
 }
 
 impl PolygonMorph {
@@ -288,8 +1209,15 @@ impl PolygonMorph {
             end_polygon,
             start_points,
             end_points,
+            perceptual: false,
         }
     }
+
+    /// Interpolate the colors in Oklab, see `Color::morph_oklab`.
+    pub fn perceptual(mut self) -> Self {
+        self.perceptual = true;
+        self
+    }
 }
 
 impl Animation for PolygonMorph {
@@ -304,18 +1232,39 @@ impl Animation for PolygonMorph {
             points.push((x, y));
         }
 
-        let fill_color = self
-            .start_polygon
-            .fill_color
-            .morph(&self.end_polygon.fill_color, progress);
-        let outline_color = self
-            .start_polygon
-            .outline_color
-            .morph(&self.end_polygon.outline_color, progress);
+        let morph = |start: &Color, end: &Color| {
+            if self.perceptual {
+                start.morph_oklab(end, progress)
+            } else {
+                start.morph(end, progress)
+            }
+        };
+        let fill = match (
+            &self.start_polygon.fill,
+            &self.end_polygon.fill,
+        ) {
+            (
+                objects::Fill::Solid(start),
+                objects::Fill::Solid(end),
+            ) => objects::Fill::Solid(morph(start, end)),
+            // Gradients cant be blended with each other, so swap over halfway.
+            (start, end) => {
+                if progress < 0.5 {
+                    start.clone()
+                } else {
+                    end.clone()
+                }
+            }
+        };
+        let outline_color = morph(
+            &self.start_polygon.outline_color,
+            &self.end_polygon.outline_color,
+        );
 
-        let polygon = objects::Polygon::new(points)
-            .fill(fill_color)
+        let mut polygon = objects::Polygon::new(points)
+            .fill(fill)
             .outline(outline_color);
+        polygon.precision = self.start_polygon.precision;
 
         polygon.render()
     }
@@ -361,7 +1310,7 @@ fn create_missing_points(
             .iter()
             .map(|(a, b)| cast_to_line(*a, *b, point))
             .enumerate()
-            .min_by(|(_, a), (_, b)| a.1.partial_cmp(&b.1).unwrap())
+            .min_by(|(_, a), (_, b)| a.1.total_cmp(&b.1))
             .unwrap();
         segments[i].push((li, closet));
     }
@@ -387,9 +1336,7 @@ fn closest_point(point: &Point, segment: &[Point]) -> (usize, Point) {
         .copied()
         .enumerate()
         .min_by(|(_, a), (_, b)| {
-            distance(*point, *a)
-                .partial_cmp(&distance(*point, *b))
-                .unwrap()
+            distance(*point, *a).total_cmp(&distance(*point, *b))
         })
         .unwrap()
 }
@@ -456,19 +1403,92 @@ fn top_left(points: &[Point]) -> Point {
 }
 
 /// An animation that types out the text.
-pub struct TextType(pub Arc<objects::Text>);
+pub struct TextType {
+    /// The text to type out.
+    pub text: Arc<objects::Text>,
+    /// The cursor shown after the typed characters.
+    pub cursor: Cursor,
+    /// How many times per second the cursor blinks, 0.0 keeps it visible.
+    pub blink_hz: f32,
+    /// Whether the cursor stays after the text is fully typed.
+    pub keep_cursor: bool,
+}
+
+/// The look of the cursor of `TextType`.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum Cursor {
+    /// An underscore, `_`.
+    #[default]
+    Underscore,
+    /// A block, `▋`.
+    Block,
+    /// A vertical bar, `|`.
+    Bar,
+}
+
+impl Cursor {
+    /// The character drawn for the cursor.
+    fn as_char(self) -> char {
+        match self {
+            Self::Underscore => '_',
+            Self::Block => '▋',
+            Self::Bar => '|',
+        }
+    }
+}
+
+impl TextType {
+    /// Create a new `TextType` with a steady underscore cursor.
+    pub fn new(text: Arc<objects::Text>) -> Self {
+        Self {
+            text,
+            cursor: Cursor::default(),
+            blink_hz: 0.0,
+            keep_cursor: false,
+        }
+    }
+
+    /// Sets the look of the cursor.
+    pub fn cursor(mut self, cursor: Cursor) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    /// Blink the cursor the given amount of times per second.
+    pub fn blink(mut self, hz: f32) -> Self {
+        self.blink_hz = hz;
+        self
+    }
+
+    /// Keep showing the cursor after the text is fully typed, instead of hiding it.
+    pub fn keep_cursor(mut self) -> Self {
+        self.keep_cursor = true;
+        self
+    }
+}
 
 impl Animation for TextType {
     fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
-        let mut text = (*self.0).clone();
+        self.animate_at(progress, 0.0)
+    }
+
+    fn animate_at(
+        &self,
+        progress: f32,
+        time: f32,
+    ) -> (isize, Box<dyn svg::Node>) {
+        let mut text = (*self.text).clone();
         let chars_count = text.text.chars().count();
         let chars_done =
             (chars_count as f32 * progress).floor() as usize;
         let mut chars =
             text.text.chars().take(chars_done).collect::<String>();
 
-        if chars_done != chars_count {
-            chars.push('_');
+        let typing = chars_done < chars_count;
+        let blink_on = self.blink_hz <= 0.0
+            || (time * self.blink_hz).fract() < 0.5;
+        if (typing || self.keep_cursor) && blink_on {
+            chars.push(self.cursor.as_char());
         }
 
         text.text = chars;
@@ -476,6 +1496,175 @@ impl Animation for TextType {
     }
 }
 
+/// An animation that fades in the characters of the text one after another.
+///
+/// Unlike `TextType` every character is at its final position from the start,
+/// each character fades in over its own slice of the animation.
+pub struct CharFade(pub Arc<objects::Text>);
+
+impl Animation for CharFade {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        let chars_count = self.0.text.chars().count();
+        let spans =
+            self.0.text.chars().enumerate().map(|(index, char)| {
+                let opacity = (progress * chars_count as f32
+                    - index as f32)
+                    .clamp(0.0, 1.0);
+                svg::node::element::TSpan::new(char)
+                    .set("fill-opacity", opacity)
+            });
+        let text = self.0.element("").add(crate::join_inline(spans));
+
+        (self.0.z_index, Box::new(text))
+    }
+}
+
+/// An animation that morphs one text into another.
+///
+/// The characters the two strings share at the start and end stay put,
+/// while the differing middle of `from` fades out as the middle of `to` fades in.
+/// The shared characters are handed over from `from` to `to` halfway through,
+/// so they only show up once even though both texts are drawn.
+///
+/// Both texts keep their own position and styling,
+/// so they should usually share an anchor and font size.
+pub struct MorphText {
+    /// The text to morph from.
+    pub from: Arc<objects::Text>,
+    /// The text to morph into.
+    pub to: Arc<objects::Text>,
+}
+
+impl MorphText {
+    /// Creates a new morph between the two texts.
+    pub fn new(
+        from: Arc<objects::Text>,
+        to: Arc<objects::Text>,
+    ) -> Self {
+        Self { from, to }
+    }
+
+    /// Creates the text element with every character as its own span.
+    ///
+    /// The shared characters use `shared_opacity`, the rest use `opacity`.
+    fn faded_element(
+        text: &objects::Text,
+        chars: &[char],
+        shared: (usize, usize),
+        shared_opacity: f32,
+        opacity: f32,
+    ) -> svg::node::element::Text {
+        let (prefix, suffix) = shared;
+        let spans = chars.iter().enumerate().map(|(index, char)| {
+            let is_shared =
+                index < prefix || index >= chars.len() - suffix;
+            let opacity =
+                if is_shared { shared_opacity } else { opacity };
+            svg::node::element::TSpan::new(char.to_string())
+                .set("fill-opacity", opacity)
+        });
+        text.element("").add(crate::join_inline(spans))
+    }
+}
+
+impl Animation for MorphText {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        if progress <= 0.0 {
+            return self.from.render();
+        }
+        if progress >= 1.0 {
+            return self.to.render();
+        }
+
+        let from = self.from.text.chars().collect::<Vec<_>>();
+        let to = self.to.text.chars().collect::<Vec<_>>();
+
+        let prefix =
+            from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+        let max_suffix = from.len().min(to.len()) - prefix;
+        let suffix = from
+            .iter()
+            .rev()
+            .zip(to.iter().rev())
+            .take(max_suffix)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let handed_over = progress >= 0.5;
+        let from_element = Self::faded_element(
+            &self.from,
+            &from,
+            (prefix, suffix),
+            if handed_over { 0.0 } else { 1.0 },
+            1.0 - progress,
+        );
+        let to_element = Self::faded_element(
+            &self.to,
+            &to,
+            (prefix, suffix),
+            if handed_over { 1.0 } else { 0.0 },
+            progress,
+        );
+
+        let group = svg::node::element::Group::new()
+            .add(from_element)
+            .add(to_element);
+        (self.from.z_index.max(self.to.z_index), Box::new(group))
+    }
+}
+
+/// An animation that counts down from `start` to 1, one number per `step` seconds.
+///
+/// Each number pulses in with a small scale animation.
+/// The container created by `container` has the duration of the whole countdown.
+pub struct Countdown {
+    /// The text used to display the numbers, its content is replaced by the current number.
+    pub text: objects::Text,
+    /// The number to start counting down from.
+    pub start: u32,
+    /// The duration each number is shown for in seconds.
+    pub step: f32,
+}
+
+impl Countdown {
+    /// Create a new `Countdown` displaying the numbers using the given text style.
+    pub fn new(text: objects::Text, start: u32, step: f32) -> Self {
+        Self { text, start, step }
+    }
+}
+
+impl Animation for Countdown {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        /// How much bigger the number is at the start of its tick.
+        const PULSE_SCALE: f32 = 0.3;
+
+        let ticks = progress * self.start as f32;
+        let tick =
+            (ticks.floor() as u32).min(self.start.saturating_sub(1));
+        let tick_progress = (ticks - tick as f32).clamp(0.0, 1.0);
+
+        let mut text = self.text.clone();
+        text.text = (self.start - tick).to_string();
+        let (z, node) = text.render();
+
+        let scale = 1.0 + PULSE_SCALE * (1.0 - tick_progress).powi(2);
+        let group = svg::node::element::Group::new().add(node).set(
+            "transform",
+            format!(
+                "translate({}, {}) scale({}) translate({}, {})",
+                text.x, text.y, scale, -text.x, -text.y
+            ),
+        );
+
+        (z, Box::new(group))
+    }
+
+    fn container(self) -> AnimationContainer {
+        let duration = self.start as f32 * self.step;
+        AnimationContainer::new(Arc::new(self)).duration(duration)
+    }
+}
+
 /// An animation that writes out the text by drawing the path of the text.
 /// Similar to `PolygonDraw` but for each segment of the characters.
 ///
@@ -495,8 +1684,9 @@ impl TextWrite {
 
 impl Animation for TextWrite {
     fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
-        let amount_segments =
-            (self.0.len() as f32 * progress).floor() as usize;
+        let amount_segments = ((self.0.len() as f32 * progress)
+            .floor() as usize)
+            .min(self.0.len());
         let path = self.0[..amount_segments].join(" ");
 
         let path = svg::node::element::Path::new()
@@ -513,7 +1703,12 @@ fn calculate_path_segements_from_text(
     node: Box<dyn svg::Node>,
 ) -> Vec<String> {
     let doc = svg::Document::new().add(node);
-    let node = crate::convert_to_resvg(doc.to_string());
+    let Ok(node) = crate::convert_to_resvg(
+        doc.to_string(),
+        crate::system_font_database(),
+    ) else {
+        return Vec::new();
+    };
     let node = node.root().children()[0].clone();
 
     let resvg::usvg::Node::Text(node) = node else {
@@ -551,6 +1746,335 @@ fn calculate_path_segements_from_text(
     string_segments
 }
 
+/// Sends a short highlight along the outline of the object, like manim's `ShowPassingFlash`.
+///
+/// The object itself is left unchanged, the highlight is drawn on top of it.
+/// Every contour of the object (like each glyph of a text) gets its own highlight, all moving at once.
+pub struct PassingFlash {
+    /// The pre-rendered object.
+    object: (isize, Box<dyn svg::Node>),
+    /// The path data and length of each contour of the object.
+    contours: Vec<(String, f32)>,
+    /// The length of the highlight, as a fraction of the contour length.
+    width: f32,
+    /// The color of the highlight.
+    color: Color,
+}
+
+impl PassingFlash {
+    /// Create a new `PassingFlash` for the given object.
+    ///
+    /// `width` is the length of the highlight as a fraction of the outline, so `0.1` covers a tenth of it.
+    pub fn new(object: &impl Object, width: f32) -> Self {
+        let (z, node) = object.render();
+        let contours = crate::flatten_paths(node.clone())
+            .iter()
+            .flat_map(split_contours)
+            .map(|contour| {
+                (crate::path_data(&contour), path_length(&contour))
+            })
+            .collect();
+
+        Self {
+            object: (z, node),
+            contours,
+            width: width.clamp(0.0, 1.0),
+            color: Color::rgb(255, 255, 0),
+        }
+    }
+
+    /// Sets the color of the highlight, defaults to yellow.
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+impl Animation for PassingFlash {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        /// The stroke width of the highlight.
+        const FLASH_STROKE_WIDTH: f32 = 5.0;
+
+        let mut group = svg::node::element::Group::new()
+            .add(self.object.1.clone());
+
+        for (data, length) in &self.contours {
+            // The highlight enters before the start of the contour and leaves past the end,
+            // with the gap long enough that only a single dash is ever visible.
+            let dash = length * self.width;
+            let start = progress * (length + dash) - dash;
+            group = group.add(
+                svg::node::element::Path::new()
+                    .set("d", data.as_str())
+                    .set("fill", "none")
+                    .set("stroke", self.color.as_css().as_ref())
+                    .set("stroke-width", FLASH_STROKE_WIDTH)
+                    .set("stroke-linecap", "round")
+                    .set(
+                        "stroke-dasharray",
+                        format!("{dash} {length}"),
+                    )
+                    .set("stroke-dashoffset", -start),
+            );
+        }
+
+        (self.object.0, Box::new(group))
+    }
+}
+
+/// Split a path into its contours, each starting at a move.
+fn split_contours(
+    path: &resvg::usvg::tiny_skia_path::Path,
+) -> Vec<resvg::usvg::tiny_skia_path::Path> {
+    use resvg::usvg::tiny_skia_path::{PathBuilder, PathSegment};
+
+    let mut contours = Vec::new();
+    let mut builder = PathBuilder::new();
+    for segment in path.segments() {
+        match segment {
+            PathSegment::MoveTo(p) => {
+                contours.extend(
+                    std::mem::replace(
+                        &mut builder,
+                        PathBuilder::new(),
+                    )
+                    .finish(),
+                );
+                builder.move_to(p.x, p.y);
+            }
+            PathSegment::LineTo(p) => builder.line_to(p.x, p.y),
+            PathSegment::QuadTo(p1, p) => {
+                builder.quad_to(p1.x, p1.y, p.x, p.y)
+            }
+            PathSegment::CubicTo(p1, p2, p) => {
+                builder.cubic_to(p1.x, p1.y, p2.x, p2.y, p.x, p.y)
+            }
+            PathSegment::Close => builder.close(),
+        }
+    }
+    contours.extend(builder.finish());
+    contours
+}
+
+/// The length of a path, curves are measured by splitting them into short lines.
+fn path_length(path: &resvg::usvg::tiny_skia_path::Path) -> f32 {
+    path_polylines(path)
+        .iter()
+        .map(|points| {
+            points
+                .windows(2)
+                .map(|pair| distance(pair[0], pair[1]))
+                .sum::<f32>()
+        })
+        .sum()
+}
+
+/// Split a path into one line of points per contour, curves are split into short lines.
+///
+/// Closed contours end with their first point again.
+fn path_polylines(
+    path: &resvg::usvg::tiny_skia_path::Path,
+) -> Vec<Vec<Point>> {
+    use resvg::usvg::tiny_skia_path::PathSegment;
+
+    /// The amount of lines each curve is split into.
+    const CURVE_STEPS: usize = 16;
+
+    let mut polylines: Vec<Vec<Point>> = Vec::new();
+    let mut current = (0.0, 0.0);
+    let mut contour_start = (0.0, 0.0);
+    for segment in path.segments() {
+        let curve: Box<dyn Fn(f32) -> Point> = match segment {
+            PathSegment::MoveTo(p) => {
+                current = (p.x, p.y);
+                contour_start = current;
+                polylines.push(vec![current]);
+                continue;
+            }
+            PathSegment::LineTo(p) => {
+                current = (p.x, p.y);
+                Box::new(move |_| current)
+            }
+            PathSegment::Close => {
+                current = contour_start;
+                Box::new(move |_| contour_start)
+            }
+            PathSegment::QuadTo(p1, p) => {
+                let p0 = current;
+                Box::new(move |t| {
+                    let u = 1.0 - t;
+                    (
+                        u * u * p0.0
+                            + 2.0 * u * t * p1.x
+                            + t * t * p.x,
+                        u * u * p0.1
+                            + 2.0 * u * t * p1.y
+                            + t * t * p.y,
+                    )
+                })
+            }
+            PathSegment::CubicTo(p1, p2, p) => {
+                let p0 = current;
+                Box::new(move |t| {
+                    let u = 1.0 - t;
+                    (
+                        u * u * u * p0.0
+                            + 3.0 * u * u * t * p1.x
+                            + 3.0 * u * t * t * p2.x
+                            + t * t * t * p.x,
+                        u * u * u * p0.1
+                            + 3.0 * u * u * t * p1.y
+                            + 3.0 * u * t * t * p2.y
+                            + t * t * t * p.y,
+                    )
+                })
+            }
+        };
+
+        if polylines.is_empty() {
+            polylines.push(vec![contour_start]);
+        }
+        let polyline = polylines.last_mut().unwrap();
+        let steps = match segment {
+            PathSegment::LineTo(_) | PathSegment::Close => 1,
+            _ => CURVE_STEPS,
+        };
+        for step in 1..=steps {
+            current = curve(step as f32 / steps as f32);
+            polyline.push(current);
+        }
+    }
+    polylines
+}
+
+/// Morphs the outline of any object into the outline of another object.
+///
+/// Both objects are flattened to paths, like `TextWrite` and `PassingFlash` do,
+/// so this also morphs text and math into shapes.
+/// Every contour is matched with the contour at the same index of the other object,
+/// and the points are matched like `PolygonMorph` does.
+/// Contours without a partner shrink into (or grow out of) their own center.
+///
+/// The styling of the objects is lost in the flattening,
+/// so the morph is filled with the colors set by `colors`.
+pub struct PathMorph {
+    /// The matched `(start, end)` points of every contour.
+    contours: Vec<(Vec<Point>, Vec<Point>)>,
+    /// The fill color at the start of the morph.
+    from_color: Color,
+    /// The fill color at the end of the morph.
+    to_color: Color,
+    /// The z-index of the morph.
+    z_index: isize,
+}
+
+impl PathMorph {
+    /// Create a new `PathMorph` between the given objects.
+    ///
+    /// The z-index is taken from `from`.
+    pub fn new(from: Arc<dyn Object>, to: Arc<dyn Object>) -> Self {
+        /// Flatten the object into the points of its contours.
+        fn contours(object: &dyn Object) -> (isize, Vec<Vec<Point>>) {
+            let (z_index, node) = object.render();
+            let contours = crate::flatten_paths(node)
+                .iter()
+                .flat_map(path_polylines)
+                .map(|mut points| {
+                    if points.len() > 1
+                        && points.first() == points.last()
+                    {
+                        points.pop();
+                    }
+                    points
+                })
+                .collect();
+            (z_index, contours)
+        }
+
+        /// The center of the given points.
+        fn centroid(points: &[Point]) -> Vec<Point> {
+            let count = points.len().max(1) as f32;
+            let (x, y) = points.iter().fold((0.0, 0.0), |acc, p| {
+                (acc.0 + p.0, acc.1 + p.1)
+            });
+            vec![(x / count, y / count)]
+        }
+
+        let (z_index, from) = contours(from.as_ref());
+        let (_, to) = contours(to.as_ref());
+
+        let count = from.len().max(to.len());
+        let contours = (0..count)
+            .map(|index| {
+                let mut start = from
+                    .get(index)
+                    .cloned()
+                    .unwrap_or_else(|| centroid(&to[index]));
+                let mut end = to
+                    .get(index)
+                    .cloned()
+                    .unwrap_or_else(|| centroid(&from[index]));
+
+                match start.len().cmp(&end.len()) {
+                    std::cmp::Ordering::Less => {
+                        create_missing_points(&mut start, &mut end);
+                    }
+                    std::cmp::Ordering::Greater => {
+                        create_missing_points(&mut end, &mut start);
+                    }
+                    std::cmp::Ordering::Equal => {}
+                }
+                (start, end)
+            })
+            .collect();
+
+        Self {
+            contours,
+            from_color: Color::rgb(255, 255, 255),
+            to_color: Color::rgb(255, 255, 255),
+            z_index,
+        }
+    }
+
+    /// Sets the fill color at the start and the end of the morph.
+    pub fn colors(mut self, from: Color, to: Color) -> Self {
+        self.from_color = from;
+        self.to_color = to;
+        self
+    }
+}
+
+impl Animation for PathMorph {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        let data = self
+            .contours
+            .iter()
+            .map(|(start, end)| {
+                let points = start
+                    .iter()
+                    .zip(end)
+                    .map(|(start, end)| {
+                        format!(
+                            "{} {}",
+                            start.0 + (end.0 - start.0) * progress,
+                            start.1 + (end.1 - start.1) * progress
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                format!("M{} Z", points.join(" L"))
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let color = self.from_color.morph(&self.to_color, progress);
+        let path = svg::node::element::Path::new()
+            .set("d", data)
+            .set("fill", color.as_css().as_ref());
+
+        (self.z_index, Box::new(path))
+    }
+}
+
 /// Fade in with a gradient from left to right.
 pub struct FadeGradient(isize, Box<dyn svg::Node>);
 
@@ -626,6 +2150,7 @@ impl SvgTyper {
 
 impl Animation for SvgTyper {
     fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        let progress = progress.clamp(0.0, 1.0);
         let nodes =
             (self.total_nodes as f32 * progress).floor() as usize;
         let segment_progress =
@@ -739,3 +2264,437 @@ fn slice_events(
 
     Box::new(top_node)
 }
+
+/// Transforms one math expression into another, keeping the parts they share.
+///
+/// The glyphs of both expressions are matched up by what they show, in reading order.
+/// Matched glyphs move (and resize) to their new place,
+/// glyphs only in the start expression fade out, and glyphs only in the end expression fade in.
+///
+/// Shared glyphs only stay still if the layout puts them in the same place,
+/// `Math::center_on` centers the whole expression so a wider or taller result shifts everything,
+/// place both expressions with `Math::baseline_at` to keep them still.
+pub struct MathTransform {
+    /// The z-index of the animation, taken from the end expression.
+    z_index: isize,
+    /// Glyphs present in both expressions, as start and end glyph.
+    moved: Vec<(objects::Glyph, objects::Glyph)>,
+    /// Glyphs only present in the start expression.
+    removed: Vec<objects::Glyph>,
+    /// Glyphs only present in the end expression.
+    added: Vec<objects::Glyph>,
+}
+
+impl MathTransform {
+    /// Create a new `MathTransform` between the given expressions.
+    pub fn new(start: &objects::Math, end: &objects::Math) -> Self {
+        let start_glyphs = start.glyphs();
+        let end_glyphs = end.glyphs();
+
+        // Longest common subsequence of the glyph keys.
+        let mut lengths = vec![
+            vec![0usize; end_glyphs.len() + 1];
+            start_glyphs.len() + 1
+        ];
+        for (i, start_glyph) in start_glyphs.iter().enumerate().rev()
+        {
+            for (j, end_glyph) in end_glyphs.iter().enumerate().rev()
+            {
+                lengths[i][j] = if start_glyph.key == end_glyph.key {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+
+        let mut moved = Vec::new();
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < start_glyphs.len() && j < end_glyphs.len() {
+            if start_glyphs[i].key == end_glyphs[j].key {
+                moved.push((
+                    start_glyphs[i].clone(),
+                    end_glyphs[j].clone(),
+                ));
+                i += 1;
+                j += 1;
+            } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+                removed.push(start_glyphs[i].clone());
+                i += 1;
+            } else {
+                added.push(end_glyphs[j].clone());
+                j += 1;
+            }
+        }
+        removed.extend_from_slice(&start_glyphs[i..]);
+        added.extend_from_slice(&end_glyphs[j..]);
+
+        Self {
+            z_index: end.z_index,
+            moved,
+            removed,
+            added,
+        }
+    }
+}
+
+impl Animation for MathTransform {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        /// Draw a glyph with the given transform and color.
+        fn glyph_path(
+            glyph: &objects::Glyph,
+            transform: resvg::usvg::Transform,
+            color: Color,
+        ) -> svg::node::element::Path {
+            svg::node::element::Path::new()
+                .set("d", glyph.data.as_str())
+                .set(
+                    "transform",
+                    format!(
+                        "matrix({} {} {} {} {} {})",
+                        transform.sx,
+                        transform.ky,
+                        transform.kx,
+                        transform.sy,
+                        transform.tx,
+                        transform.ty
+                    ),
+                )
+                .set("fill", color.as_css().as_ref())
+        }
+
+        /// Scale the alpha of the color by the given amount.
+        fn faded(color: Color, amount: f32) -> Color {
+            color.with_alpha((color.a() as f32 * amount) as u8)
+        }
+
+        let lerp = |a: f32, b: f32| a + (b - a) * progress;
+        let mut group = svg::node::element::Group::new();
+
+        // Fully faded glyphs are left out entirely.
+        if progress < 1.0 {
+            for glyph in &self.removed {
+                group = group.add(glyph_path(
+                    glyph,
+                    glyph.transform,
+                    faded(glyph.color, 1.0 - progress),
+                ));
+            }
+        }
+        if progress > 0.0 {
+            for glyph in &self.added {
+                group = group.add(glyph_path(
+                    glyph,
+                    glyph.transform,
+                    faded(glyph.color, progress),
+                ));
+            }
+        }
+        for (start, end) in &self.moved {
+            let (a, b) = (start.transform, end.transform);
+            let transform = resvg::usvg::Transform::from_row(
+                lerp(a.sx, b.sx),
+                lerp(a.ky, b.ky),
+                lerp(a.kx, b.kx),
+                lerp(a.sy, b.sy),
+                lerp(a.tx, b.tx),
+                lerp(a.ty, b.ty),
+            );
+            group = group.add(glyph_path(
+                start,
+                transform,
+                start.color.morph(&end.color, progress),
+            ));
+        }
+
+        (self.z_index, Box::new(group))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renders the progress it was given, so tests can read it back from the output.
+    struct Probe;
+
+    impl Animation for Probe {
+        fn animate(
+            &self,
+            progress: f32,
+        ) -> (isize, Box<dyn svg::Node>) {
+            (
+                0,
+                Box::new(
+                    svg::node::element::Group::new()
+                        .set("data-progress", progress),
+                ),
+            )
+        }
+    }
+
+    /// The progress the probe inside the rendered node was given.
+    fn probed((_, node): (isize, Box<dyn svg::Node>)) -> f32 {
+        let svg = node.to_string();
+        let start = svg.find("data-progress=\"").unwrap() + 15;
+        let end = start + svg[start..].find('"').unwrap();
+        svg[start..end].parse().unwrap()
+    }
+
+    /// The points of the outline drawn so far, `None` once the whole polygon is drawn.
+    fn drawn_points(
+        (_, node): (isize, Box<dyn svg::Node>),
+    ) -> Option<Vec<Point>> {
+        let svg = node.to_string();
+        let polyline = &svg[svg.find("<polyline")?..];
+        let start = polyline.find("points=\"").unwrap() + 8;
+        let end = start + polyline[start..].find('"').unwrap();
+        Some(
+            polyline[start..end]
+                .split(' ')
+                .map(|point| {
+                    let (x, y) = point.split_once(',').unwrap();
+                    (x.parse().unwrap(), y.parse().unwrap())
+                })
+                .collect(),
+        )
+    }
+
+    /// The length of the line through the points.
+    fn drawn_length(points: &[Point]) -> f32 {
+        points
+            .windows(2)
+            .map(|pair| distance(pair[0], pair[1]))
+            .sum()
+    }
+
+    /// Whether the points are within rounding error of each other.
+    fn close_to(a: Point, b: Point) -> bool {
+        distance(a, b) < 1e-3
+    }
+
+    #[test]
+    fn zero_duration_is_complete() {
+        let container = Probe.container().duration(0.0);
+        for time in [-1.0, 0.0, 1.0] {
+            let progress = probed(container.animate(time));
+            assert_eq!(progress, 1.0);
+        }
+    }
+
+    #[test]
+    fn polygon_draw_animates_the_closing_edge() {
+        let square = Arc::new(objects::Polygon::new(vec![
+            (0.0, 0.0),
+            (100.0, 0.0),
+            (100.0, 100.0),
+            (0.0, 100.0),
+        ]));
+        let draw = PolygonDraw(square);
+        let count = |progress| {
+            drawn_points(draw.animate(progress))
+                .map(|points| points.len())
+        };
+
+        assert_eq!(count(0.0), Some(2));
+        assert_eq!(count(0.25), Some(3));
+        assert_eq!(count(0.5), Some(4));
+        assert_eq!(count(0.99), Some(5));
+        // At the end the closed polygon is drawn as is.
+        assert_eq!(count(1.0), None);
+
+        // Halfway along the closing edge, instead of snapping to either end of it.
+        let points = drawn_points(draw.animate(0.875)).unwrap();
+        assert!(close_to(*points.last().unwrap(), (0.0, 50.0)));
+        let points = drawn_points(draw.animate(0.99)).unwrap();
+        assert!(close_to(*points.last().unwrap(), (0.0, 4.0)));
+    }
+
+    #[test]
+    fn polygon_trace_moves_by_length() {
+        let rectangle = Arc::new(objects::Polygon::new(vec![
+            (0.0, 0.0),
+            (300.0, 0.0),
+            (300.0, 100.0),
+            (0.0, 100.0),
+        ]));
+        let trace = PolygonTrace(rectangle);
+
+        let points = drawn_points(trace.animate(0.5)).unwrap();
+        assert!((drawn_length(&points) - 400.0).abs() < 1e-3);
+
+        // Counting vertices would be at the end of the long edge here, a quarter of the length is not.
+        let points = drawn_points(trace.animate(0.25)).unwrap();
+        assert!((drawn_length(&points) - 200.0).abs() < 1e-3);
+        assert!(close_to(*points.last().unwrap(), (200.0, 0.0)));
+    }
+
+    #[test]
+    fn repeat_restarts_each_cycle() {
+        let container = Probe.container().duration(1.0).repeat(2);
+        assert_eq!(probed(container.animate(0.25)), 0.5);
+        assert_eq!(probed(container.animate(0.5)), 0.0);
+        assert_eq!(probed(container.animate(0.75)), 0.5);
+        assert_eq!(probed(container.animate(1.0)), 1.0);
+    }
+
+    #[test]
+    fn repeat_forever_loops_by_time() {
+        let container = Probe
+            .container()
+            .duration(1.0)
+            .repeat_forever()
+            .duration(3.0);
+        assert_eq!(probed(container.animate(0.25)), 0.25);
+        assert_eq!(probed(container.animate(2.25)), 0.25);
+    }
+
+    #[test]
+    fn yoyo_turns_around_halfway() {
+        let container = Probe.container().duration(1.0).yoyo();
+        assert_eq!(probed(container.animate(0.0)), 0.0);
+        assert_eq!(probed(container.animate(0.25)), 0.5);
+        assert_eq!(probed(container.animate(0.5)), 1.0);
+        assert_eq!(probed(container.animate(0.75)), 0.5);
+        assert_eq!(probed(container.animate(1.0)), 0.0);
+
+        // Each repeat goes forward and back again.
+        let container =
+            Probe.container().duration(1.0).yoyo().repeat(2);
+        assert_eq!(probed(container.animate(0.25)), 1.0);
+        assert_eq!(probed(container.animate(0.5)), 0.0);
+    }
+
+    #[test]
+    fn sequence_hands_over_at_the_weighted_boundary() {
+        let sequence = Sequence::weighted(vec![
+            (Arc::new(Probe) as Arc<dyn Animation>, 1.0),
+            (Arc::new(Probe), 3.0),
+        ]);
+
+        assert_eq!(probed(sequence.animate(0.0)), 0.0);
+        // The first animation finishes right as the second one starts.
+        let before = probed(sequence.animate(0.25 - 1e-4));
+        assert!((before - 1.0).abs() < 1e-3);
+        assert_eq!(probed(sequence.animate(0.25)), 0.0);
+        assert!((probed(sequence.animate(0.625)) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn sequence_ends_at_exactly_one() {
+        // These weights dont sum to exactly 1.0 in floating point.
+        let sequence = Sequence::weighted(vec![
+            (Arc::new(Probe) as Arc<dyn Animation>, 0.1),
+            (Arc::new(Probe), 0.2),
+            (Arc::new(Probe), 0.7),
+        ]);
+        assert_eq!(probed(sequence.animate(1.0)), 1.0);
+        assert!(probed(sequence.animate(1.0 - 1e-6)) <= 1.0);
+    }
+
+    #[test]
+    fn math_transform_keeps_shared_prefix_still() {
+        let start = objects::Math::new("a+b").baseline_at(0.0, 0.0);
+        let end = objects::Math::new("a+c").baseline_at(0.0, 0.0);
+        let transform = MathTransform::new(&start, &end);
+
+        assert_eq!(transform.moved.len(), 2);
+        assert_eq!(transform.removed.len(), 1);
+        assert_eq!(transform.added.len(), 1);
+        // MathJax rounds the size of each expression, so allow a sliver of a pixel.
+        for (from, to) in &transform.moved {
+            let (from, to) = (from.bounds, to.bounds);
+            assert!((from.left() - to.left()).abs() < 1e-2);
+            assert!((from.top() - to.top()).abs() < 1e-2);
+            assert!((from.right() - to.right()).abs() < 1e-2);
+            assert!((from.bottom() - to.bottom()).abs() < 1e-2);
+        }
+    }
+
+    /// The points of the single contour a `PathMorph` drew.
+    fn morphed_points(
+        (_, node): (isize, Box<dyn svg::Node>),
+    ) -> Vec<Point> {
+        let svg = node.to_string();
+        let start = svg.find("d=\"M").unwrap() + 4;
+        let end = start + svg[start..].find(" Z").unwrap();
+        svg[start..end]
+            .split(" L")
+            .map(|point| {
+                let (x, y) = point.split_once(' ').unwrap();
+                (x.parse().unwrap(), y.parse().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn path_morph_interpolates_square_to_triangle() {
+        let square =
+            [(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)];
+        let triangle = [(0.0, 0.0), (100.0, 0.0), (50.0, 100.0)];
+        let morph = PathMorph::new(
+            Arc::new(objects::Polygon::new(square)),
+            Arc::new(objects::Polygon::new(triangle)),
+        );
+
+        let start = morphed_points(morph.animate(0.0));
+        let end = morphed_points(morph.animate(1.0));
+        let middle = morphed_points(morph.animate(0.5));
+        assert_eq!(start.len(), 4);
+        assert_eq!(end.len(), 4);
+        assert_eq!(middle.len(), 4);
+
+        for corner in square {
+            assert!(start
+                .iter()
+                .any(|point| close_to(*point, corner)));
+        }
+        // The extra point is put on an edge of the triangle.
+        for point in &end {
+            let on_outline = (0..3).any(|index| {
+                let (_, away) = cast_to_line(
+                    triangle[index],
+                    triangle[(index + 1) % 3],
+                    *point,
+                );
+                away < 1e-3
+            });
+            assert!(on_outline);
+        }
+        for corner in triangle {
+            assert!(end.iter().any(|point| close_to(*point, corner)));
+        }
+
+        for ((start, end), middle) in
+            start.iter().zip(&end).zip(&middle)
+        {
+            let halfway =
+                ((start.0 + end.0) / 2.0, (start.1 + end.1) / 2.0);
+            assert!(close_to(*middle, halfway));
+        }
+        // The square corners that the triangle doesnt share have moved.
+        assert!(start
+            .iter()
+            .zip(&middle)
+            .any(|(start, middle)| !close_to(*start, *middle)));
+    }
+
+    #[test]
+    fn sequence_forwards_the_time() {
+        let looping = LoopAnimation {
+            animation: Arc::new(Probe),
+            period: 1.0,
+        };
+        let container =
+            Sequence::new(vec![Arc::new(Probe), Arc::new(looping)])
+                .container()
+                .duration(4.0);
+
+        assert_eq!(probed(container.animate(1.0)), 0.5);
+        // The looping half follows the time, not its share of the progress.
+        assert_eq!(probed(container.animate(2.25)), 0.25);
+        assert_eq!(probed(container.animate(3.5)), 0.5);
+    }
+}
@@ -8,9 +8,6 @@
 
 #![warn(missing_docs, clippy::missing_docs_in_private_items)]
 
-#[cfg(feature = "progress")]
-use indicatif::{ParallelProgressIterator, ProgressIterator};
-
 use rayon::prelude::*;
 use std::sync::Arc;
 
@@ -20,13 +17,20 @@ pub use svg;
 
 pub mod animations;
 pub mod objects;
+pub mod prelude;
 
 /// A color with red, green, blue and alpha components.
 #[derive(Clone, Copy)]
 pub struct Color(pub u8, pub u8, pub u8, pub u8);
 
 impl Color {
-    /// Darkens the color by a certain amount.
+    /// Darkens the color by multiplying each channel by `amount`.
+    ///
+    /// `amount` is the fraction of the color to keep, clamped to 0.0 to 1.0,
+    /// so `darken(1.0)` leaves the color unchanged and `darken(0.0)` is black.
+    /// This is the same as mixing toward black by `1.0 - amount`,
+    /// note that it runs the other way from `lighten`.
+    /// The alpha component is left untouched.
     pub fn darken(&self, amount: f32) -> Self {
         let amount = amount.clamp(0.0, 1.0);
         let r = (self.0 as f32 * amount) as u8;
@@ -35,6 +39,16 @@ impl Color {
         Self(r, g, b, self.3)
     }
 
+    /// Lightens the color by moving each channel toward 255 by `amount`.
+    ///
+    /// `amount` is clamped to 0.0 to 1.0,
+    /// `lighten(0.0)` leaves the color unchanged and `lighten(1.0)` is white.
+    /// The alpha component is left untouched.
+    pub fn lighten(&self, amount: f32) -> Self {
+        let amount = amount.clamp(0.0, 1.0);
+        self.morph(&Self(255, 255, 255, self.3), amount)
+    }
+
     /// Linearly interpolates between two colors.
     fn morph(&self, other: &Self, progress: f32) -> Self {
         let r = (self.0 as f32
@@ -52,6 +66,63 @@ impl Color {
         Self(r, g, b, a)
     }
 
+    /// Inverts the color channels, the alpha component is left untouched.
+    pub const fn invert(&self) -> Self {
+        Self(255 - self.0, 255 - self.1, 255 - self.2, self.3)
+    }
+
+    /// Interpolates between two colors in the Oklab color space.
+    ///
+    /// Unlike `morph`, which interpolates the raw sRGB channels,
+    /// this keeps the transition between saturated colors from passing through muddy grays.
+    /// The alpha component is interpolated linearly.
+    pub fn morph_oklab(&self, other: &Self, progress: f32) -> Self {
+        let start = srgb_to_oklab(*self);
+        let end = srgb_to_oklab(*other);
+        let lerp = |a: f32, b: f32| a + (b - a) * progress;
+
+        let [r, g, b] = oklab_to_srgb([
+            lerp(start[0], end[0]),
+            lerp(start[1], end[1]),
+            lerp(start[2], end[2]),
+        ]);
+        let a = lerp(self.3 as f32, other.3 as f32).round() as u8;
+        Self(r, g, b, a)
+    }
+
+    /// Mixes several colors together, weighted by the given weights.
+    ///
+    /// The weights are normalized, so they do not need to add up to 1.
+    /// The color channels are blended in linear light rather than sRGB,
+    /// which avoids the muddy darker colors of naive sRGB blending.
+    /// Returns a fully transparent black if there are no colors or all weights are zero.
+    pub fn mix(colors: &[(Color, f32)]) -> Self {
+        let total_weight = colors
+            .iter()
+            .map(|(_, weight)| weight.max(0.0))
+            .sum::<f32>();
+        if total_weight <= 0.0 {
+            return Self(0, 0, 0, 0);
+        }
+
+        let mut linear = [0.0; 3];
+        let mut alpha = 0.0;
+        for (color, weight) in colors {
+            let weight = weight.max(0.0) / total_weight;
+            linear[0] += srgb_to_linear(color.0) * weight;
+            linear[1] += srgb_to_linear(color.1) * weight;
+            linear[2] += srgb_to_linear(color.2) * weight;
+            alpha += color.3 as f32 * weight;
+        }
+
+        Self(
+            linear_to_srgb(linear[0]),
+            linear_to_srgb(linear[1]),
+            linear_to_srgb(linear[2]),
+            alpha.round() as u8,
+        )
+    }
+
     /// Creates a new color with the given red, green and blue components.
     ///
     /// The alpha component is set to 255.
@@ -59,18 +130,272 @@ impl Color {
         Self(r, g, b, 255)
     }
 
+    /// The red component.
+    pub const fn r(self) -> u8 {
+        self.0
+    }
+
+    /// The green component.
+    pub const fn g(self) -> u8 {
+        self.1
+    }
+
+    /// The blue component.
+    pub const fn b(self) -> u8 {
+        self.2
+    }
+
+    /// The alpha component, 255 is fully opaque.
+    pub const fn a(self) -> u8 {
+        self.3
+    }
+
+    /// The red component as a fraction between 0.0 and 1.0.
+    pub fn r_f(self) -> f32 {
+        self.0 as f32 / 255.0
+    }
+
+    /// The green component as a fraction between 0.0 and 1.0.
+    pub fn g_f(self) -> f32 {
+        self.1 as f32 / 255.0
+    }
+
+    /// The blue component as a fraction between 0.0 and 1.0.
+    pub fn b_f(self) -> f32 {
+        self.2 as f32 / 255.0
+    }
+
+    /// The alpha component as a fraction between 0.0 and 1.0, 1.0 is fully opaque.
+    pub fn a_f(self) -> f32 {
+        self.3 as f32 / 255.0
+    }
+
+    /// Returns the color with the alpha component replaced.
+    pub const fn with_alpha(self, alpha: u8) -> Self {
+        Self(self.0, self.1, self.2, alpha)
+    }
+
+    /// Returns the color with the given opacity, from 0.0 (transparent) to 1.0 (opaque).
+    pub fn with_opacity(self, opacity: f32) -> Self {
+        self.with_alpha(
+            (opacity.clamp(0.0, 1.0) * 255.0).round() as u8
+        )
+    }
+
+    /// Creates a color from a `0xRRGGBBAA` integer.
+    ///
+    /// Useful for constants, `Color::from_u32(0xc80000ff)` is the same as `Color::rgb(200, 0, 0)`.
+    pub const fn from_u32(value: u32) -> Self {
+        let [r, g, b, a] = value.to_be_bytes();
+        Self(r, g, b, a)
+    }
+
+    /// Parses a hex color like `#c80000`, the leading `#` is optional.
+    ///
+    /// Accepts the `rgb`, `rgba`, `rrggbb` and `rrggbbaa` forms,
+    /// in the short forms each digit is repeated, so `#f00` is the same as `#ff0000`.
+    pub fn from_hex(hex: &str) -> Result<Self, ColorParseError> {
+        let error = || ColorParseError {
+            input: hex.to_string(),
+        };
+
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(error());
+        }
+        let value =
+            u32::from_str_radix(digits, 16).map_err(|_| error())?;
+
+        let value = match digits.len() {
+            3 | 4 => {
+                // Give the short form without alpha a full alpha digit.
+                let value = if digits.len() == 3 {
+                    (value << 4) | 0xf
+                } else {
+                    value
+                };
+                (0..4).rev().fold(0, |expanded, nibble| {
+                    let digit = (value >> (nibble * 4)) & 0xf;
+                    (expanded << 8) | (digit * 0x11)
+                })
+            }
+            6 => (value << 8) | 0xff,
+            8 => value,
+            _ => return Err(error()),
+        };
+
+        Ok(Self::from_u32(value))
+    }
+
+    /// Creates a color from hue, saturation and lightness.
+    ///
+    /// The hue is in degrees and wraps around, saturation and lightness are between 0.0 and 1.0.
+    /// The alpha component is set to 255.
+    pub fn from_hsl(h: f32, s: f32, l: f32) -> Self {
+        let s = s.clamp(0.0, 1.0);
+        let l = l.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        Self::from_hue_chroma(h, chroma, l - chroma / 2.0)
+    }
+
+    /// Creates a color from hue, saturation and value.
+    ///
+    /// The hue is in degrees and wraps around, saturation and value are between 0.0 and 1.0.
+    /// The alpha component is set to 255.
+    pub fn from_hsv(h: f32, s: f32, v: f32) -> Self {
+        let s = s.clamp(0.0, 1.0);
+        let v = v.clamp(0.0, 1.0);
+        let chroma = v * s;
+        Self::from_hue_chroma(h, chroma, v - chroma)
+    }
+
+    /// Creates a color from a hue, the chroma, and the amount to add to every channel.
+    fn from_hue_chroma(h: f32, chroma: f32, offset: f32) -> Self {
+        let h = h.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+
+        let channel = |c: f32| ((c + offset) * 255.0).round() as u8;
+        Self::rgb(channel(r), channel(g), channel(b))
+    }
+
+    /// Converts the color to hue, saturation and lightness.
+    ///
+    /// In the same ranges as `from_hsl`, the hue is between 0.0 and 360.0.
+    /// The alpha component is not included.
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (r, g, b) = (self.r_f(), self.g_f(), self.b_f());
+
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let l = (max + min) / 2.0;
+
+        if chroma == 0.0 {
+            return (0.0, 0.0, l);
+        }
+
+        let h = if max == r {
+            ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            (b - r) / chroma + 2.0
+        } else {
+            (r - g) / chroma + 4.0
+        };
+        let s = chroma / (1.0 - (2.0 * l - 1.0).abs());
+
+        (h * 60.0, s, l)
+    }
+
     /// Converts the color to a CSS color string.
+    ///
+    /// Opaque colors use `rgb()`, anything else uses `rgba()` with
+    /// the alpha as a fraction rounded to three decimals.
     fn as_css(&self) -> String {
-        format!(
-            "rgba({}, {}, {}, {})",
-            self.0, self.1, self.2, self.3
+        if self.3 == 255 {
+            format!("rgb({}, {}, {})", self.0, self.1, self.2)
+        } else {
+            let alpha = (self.a_f() * 1000.0).round() / 1000.0;
+            format!(
+                "rgba({}, {}, {}, {})",
+                self.0, self.1, self.2, alpha
+            )
+        }
+    }
+}
+
+/// The error returned when `Color::from_hex` is given an invalid hex color.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorParseError {
+    /// The string that failed to parse.
+    pub input: String,
+}
+
+impl std::fmt::Display for ColorParseError {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} is not a valid hex color, expected 3, 4, 6 or 8 hex digits",
+            self.input
         )
     }
 }
 
+impl std::error::Error for ColorParseError {}
+
+/// Converts a sRGB channel to linear light in the range 0.0 to 1.0.
+fn srgb_to_linear(channel: u8) -> f32 {
+    let channel = channel as f32 / 255.0;
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a linear light channel in the range 0.0 to 1.0 back to sRGB.
+fn linear_to_srgb(channel: f32) -> u8 {
+    let channel = channel.clamp(0.0, 1.0);
+    let channel = if channel <= 0.0031308 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(1.0 / 2.4) - 0.055
+    };
+    (channel * 255.0).round() as u8
+}
+
+/// Converts the color channels of a sRGB color to Oklab.
+fn srgb_to_oklab(color: Color) -> [f32; 3] {
+    let r = srgb_to_linear(color.0);
+    let g = srgb_to_linear(color.1);
+    let b = srgb_to_linear(color.2);
+
+    let l =
+        (0.41222147 * r + 0.53633254 * g + 0.051445993 * b).cbrt();
+    let m = (0.2119035 * r + 0.6806995 * g + 0.10739696 * b).cbrt();
+    let s = (0.08830246 * r + 0.28171884 * g + 0.6299787 * b).cbrt();
+
+    [
+        0.21045426 * l + 0.7936178 * m - 0.004072047 * s,
+        1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+        0.025904037 * l + 0.78277177 * m - 0.80867577 * s,
+    ]
+}
+
+/// Converts an Oklab color back to sRGB channels.
+fn oklab_to_srgb([lightness, a, b]: [f32; 3]) -> [u8; 3] {
+    let l = (lightness + 0.39633778 * a + 0.21580376 * b).powi(3);
+    let m = (lightness - 0.105561346 * a - 0.06385417 * b).powi(3);
+    let s = (lightness - 0.08948418 * a - 1.2914855 * b).powi(3);
+
+    [
+        linear_to_srgb(
+            4.0767417 * l - 3.3077116 * m + 0.23096994 * s,
+        ),
+        linear_to_srgb(
+            -1.268438 * l + 2.6097574 * m - 0.34131938 * s,
+        ),
+        linear_to_srgb(
+            -0.0041960863 * l - 0.7034186 * m + 1.7076147 * s,
+        ),
+    ]
+}
+
 /// A frame holds all the info needed to render that frame.
 #[derive(Clone)]
 struct Frame {
+    /// The index of the frame in the video.
+    index: usize,
     /// The timestamp of the frame in seconds.
     time: f32,
     /// The pre-rendered objects to be rendered in the frame.
@@ -81,7 +406,8 @@ struct Frame {
 
 /// Holds all objects and animations in the video.
 ///
-/// The length of the video will be based on the end time of the last animation.
+/// The length of the video will be based on the end time of the last animation,
+/// unless set with `set_duration`.
 #[derive(Default)]
 pub struct Timeline {
     /// Static objects to be rendered in the video.
@@ -90,9 +416,46 @@ pub struct Timeline {
     ///
     /// These have a enter and exit animation.
     animations: Vec<Arc<animations::AnimatedObject>>,
+    /// The names of the layers, in the order they were created.
+    layers: Vec<String>,
+    /// The length of the video in seconds, if set explicitly.
+    duration: Option<f32>,
 }
 
+/// The amount of frames added after the last animation when the duration is not set.
+///
+/// Without them the video would stop on the last frame of the last animation,
+/// these give a short moment of the final state before the video ends.
+const TAIL_FRAMES: usize = 10;
+
 impl Timeline {
+    /// Get a handle to the named layer, creating the layer if it doesnt exist yet.
+    ///
+    /// Layers are stacked in the order they are first created, the first layer being at the bottom.
+    /// Each layer occupies its own range of z-indices,
+    /// so objects added through a later layer always render above objects in an earlier layer,
+    /// regardless of their own z-index.
+    ///
+    /// Objects added directly to the timeline share the z-range of the first layer,
+    /// their z-index is clamped to it the same way.
+    pub fn layer(&mut self, name: impl Into<String>) -> Layer<'_> {
+        let name = name.into();
+        let index =
+            match self.layers.iter().position(|layer| *layer == name)
+            {
+                Some(index) => index,
+                None => {
+                    self.layers.push(name);
+                    self.layers.len() - 1
+                }
+            };
+
+        Layer {
+            timeline: self,
+            index: index as isize,
+        }
+    }
+
     /// Add a static object to the timeline.
     ///
     /// Note: if no animations are added, then the video duration will be 0s.
@@ -100,7 +463,7 @@ impl Timeline {
         &mut self,
         object: Arc<dyn objects::Object>,
     ) -> &mut Self {
-        self.objects.push(object.render());
+        self.push_object(object, 0);
         self
     }
 
@@ -111,7 +474,7 @@ impl Timeline {
         &mut self,
         animated_object: animations::AnimatedObject,
     ) -> &mut Self {
-        self.animations.push(Arc::new(animated_object));
+        self.push_animation(&animated_object, 0);
         self
     }
 
@@ -120,27 +483,138 @@ impl Timeline {
         &mut self,
         animated_object: Arc<animations::AnimatedObject>,
     ) -> &mut Self {
-        self.animations.push(animated_object);
+        self.push_animation(&animated_object, 0);
+        self
+    }
+
+    /// Add a static object with its z-index moved into the given layer.
+    fn push_object(
+        &mut self,
+        object: Arc<dyn objects::Object>,
+        layer: isize,
+    ) {
+        let object = InLayer {
+            inner: object,
+            layer,
+        };
+        self.objects.push(objects::Object::render(&object));
+    }
+
+    /// Add an animation with the z-indices of its object and animations moved into the given layer.
+    fn push_animation(
+        &mut self,
+        animated_object: &animations::AnimatedObject,
+        layer: isize,
+    ) {
+        let in_layer =
+            |container: &animations::AnimationContainer| {
+                animations::AnimationContainer {
+                    animation: Arc::new(InLayer {
+                        inner: container.animation.clone(),
+                        layer,
+                    }),
+                    ..container.clone()
+                }
+            };
+
+        self.animations.push(Arc::new(animations::AnimatedObject {
+            object: Arc::new(InLayer {
+                inner: animated_object.object.clone(),
+                layer,
+            }),
+            enter: in_layer(&animated_object.enter),
+            exit: in_layer(&animated_object.exit),
+        }));
+    }
+
+    /// Force the length of the video to the given amount of seconds.
+    ///
+    /// Animations running past it are cut off,
+    /// and if it is longer than the animations the final state is held until the end.
+    pub fn set_duration(&mut self, seconds: f32) -> &mut Self {
+        self.duration = Some(seconds);
         self
     }
 
+    /// The length of the timeline in seconds.
+    ///
+    /// This is the duration given to `set_duration`, or else the end of the last exit animation.
+    /// An empty timeline has a duration of `0.0`.
+    /// Without a set duration the video holds the final state for a few frames past this,
+    /// see `frame_count` for the exact length.
+    pub fn duration(&self) -> f32 {
+        self.duration.unwrap_or_else(|| self.end_time())
+    }
+
+    /// The amount of frames the video will have at the given frame rate.
+    ///
+    /// Computed without building the frames, but always matches what rendering produces.
+    pub fn frame_count(&self, fps: usize) -> usize {
+        let frames = (self.duration() * fps as f32).ceil() as usize;
+        match self.duration {
+            Some(_) => frames,
+            None => frames + TAIL_FRAMES,
+        }
+    }
+
+    /// The end time of the last animation in seconds.
+    fn end_time(&self) -> f32 {
+        self.animations
+            .iter()
+            .map(|animated_object| animated_object.exit.end)
+            .max_by(f32::total_cmp)
+            .unwrap_or(0.0)
+    }
+
+    /// The time ranges of every animated object, in the order they were added.
+    ///
+    /// Static objects are present for the whole video and are not included.
+    pub fn schedule(&self) -> Vec<ObjectSchedule> {
+        self.animations
+            .iter()
+            .map(|animated_object| ObjectSchedule {
+                active: animated_object.enter.start
+                    ..animated_object.exit.end,
+                enter: animated_object.enter.start
+                    ..animated_object.enter.end,
+                steady: animated_object.enter.end
+                    ..animated_object.exit.start,
+                exit: animated_object.exit.start
+                    ..animated_object.exit.end,
+            })
+            .collect()
+    }
+
+    /// Get the labeled animations as `(start time, label)` sorted by start time.
+    fn chapters(&self) -> Vec<(f32, String)> {
+        let mut chapters = self
+            .animations
+            .iter()
+            .flat_map(|animated_object| {
+                [&animated_object.enter, &animated_object.exit]
+            })
+            .filter_map(|container| {
+                container
+                    .label
+                    .clone()
+                    .map(|label| (container.start, label))
+            })
+            .collect::<Vec<_>>();
+        chapters.sort_by(|a, b| a.0.total_cmp(&b.0));
+        chapters
+    }
+
     /// Calculate all the frames in the video.
     ///
     /// This is done by calculating the animations and objects present on each frame.
     fn calc_frames(&self, fps: usize) -> Vec<Frame> {
-        let end_time = self
-            .animations
-            .iter()
-            .map(|animated_object| animated_object.exit.end)
-            .max_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap_or(0.0);
-        let frame_count =
-            (end_time * fps as f32).ceil() as usize + 10;
+        let duration = self.duration();
+        let frame_count = self.frame_count(fps);
 
         log::info!(
             "Video will be {} frames ({:.2}s)",
             frame_count,
-            end_time
+            duration
         );
 
         let frame_duration = 1.0 / fps as f32;
@@ -151,6 +625,7 @@ impl Timeline {
             let time = frame_index as f32 * frame_duration;
             let objects = self.objects.clone();
             frames.push(Frame {
+                index: frame_index,
                 time,
                 objects,
                 animations: Vec::new(),
@@ -165,7 +640,9 @@ impl Timeline {
                 animated_object.enter.start,
                 animated_object.enter.end,
                 fps,
-            ) {
+            )
+            .take_while(|index| *index < frame_count)
+            {
                 frames[index]
                     .animations
                     .push(enter_animation.clone());
@@ -177,7 +654,9 @@ impl Timeline {
                 animated_object.exit.start,
                 animated_object.exit.end,
                 fps,
-            ) {
+            )
+            .take_while(|index| *index < frame_count)
+            {
                 frames[index].animations.push(exit_animation.clone());
             }
 
@@ -186,13 +665,224 @@ impl Timeline {
                 animated_object.enter.end,
                 animated_object.exit.start,
                 fps,
-            ) {
+            )
+            .take_while(|index| *index < frame_count)
+            {
                 frames[index].objects.push(object.clone());
             }
         }
 
         frames
     }
+
+    /// Calculate the single frame closest to `time`.
+    ///
+    /// Resolves the same animations and objects `calc_frames` would for that frame.
+    fn frame_at(&self, time: f32, fps: usize) -> Frame {
+        let frame_duration = 1.0 / fps as f32;
+        let index = (time.max(0.0) / frame_duration).round() as usize;
+
+        let mut frame = Frame {
+            index,
+            time: index as f32 * frame_duration,
+            objects: self.objects.clone(),
+            animations: Vec::new(),
+        };
+
+        for animated_object in &self.animations {
+            let (enter, exit) =
+                (&animated_object.enter, &animated_object.exit);
+            if frame_range(enter.start, enter.end, fps)
+                .contains(&index)
+            {
+                frame.animations.push(Arc::new(enter.clone()));
+            }
+            if frame_range(exit.start, exit.end, fps).contains(&index)
+            {
+                frame.animations.push(Arc::new(exit.clone()));
+            }
+            if frame_range(enter.end, exit.start, fps)
+                .contains(&index)
+            {
+                frame.objects.push(animated_object.object.render());
+            }
+        }
+
+        frame
+    }
+}
+
+/// When an animated object is on screen, in seconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectSchedule {
+    /// From the start of the enter animation to the end of the exit animation.
+    pub active: std::ops::Range<f32>,
+    /// While the enter animation plays.
+    pub enter: std::ops::Range<f32>,
+    /// Between the animations, while the object itself is shown.
+    pub steady: std::ops::Range<f32>,
+    /// While the exit animation plays.
+    pub exit: std::ops::Range<f32>,
+}
+
+/// The amount of z-indices each layer occupies.
+const LAYER_Z_RANGE: isize = 1 << 20;
+
+/// A handle to a layer of the timeline, created by `Timeline::layer`.
+///
+/// Objects and animations added through it have their z-index moved into the layer's z-range.
+pub struct Layer<'a> {
+    /// The timeline the layer belongs to.
+    timeline: &'a mut Timeline,
+    /// The position of the layer in the stack of layers.
+    index: isize,
+}
+
+impl Layer<'_> {
+    /// Add a static object to the layer.
+    pub fn add_object(
+        &mut self,
+        object: Arc<dyn objects::Object>,
+    ) -> &mut Self {
+        self.timeline.push_object(object, self.index);
+        self
+    }
+
+    /// Add an animation to the layer.
+    pub fn add_animation(
+        &mut self,
+        animated_object: animations::AnimatedObject,
+    ) -> &mut Self {
+        self.timeline.push_animation(&animated_object, self.index);
+        self
+    }
+}
+
+/// Moves the z-index of the wrapped object or animation into a layer.
+struct InLayer<T: ?Sized> {
+    /// The wrapped object or animation.
+    inner: Arc<T>,
+    /// The position of the layer in the stack of layers.
+    layer: isize,
+}
+
+impl<T: ?Sized> InLayer<T> {
+    /// Move the z-index into the layer, clamping it to the layer's z-range.
+    fn z_index(&self, z: isize) -> isize {
+        let half_range = LAYER_Z_RANGE / 2;
+        self.layer * LAYER_Z_RANGE
+            + z.clamp(-half_range, half_range - 1)
+    }
+}
+
+impl objects::Object for InLayer<dyn objects::Object> {
+    fn render(&self) -> (isize, Box<dyn svg::Node>) {
+        let (z, node) = self.inner.render();
+        (self.z_index(z), node)
+    }
+}
+
+impl animations::Animation for InLayer<dyn animations::Animation> {
+    fn animate(&self, progress: f32) -> (isize, Box<dyn svg::Node>) {
+        let (z, node) = self.inner.animate(progress);
+        (self.z_index(z), node)
+    }
+
+    fn animate_at(
+        &self,
+        progress: f32,
+        time: f32,
+    ) -> (isize, Box<dyn svg::Node>) {
+        let (z, node) = self.inner.animate_at(progress, time);
+        (self.z_index(z), node)
+    }
+}
+
+/// Reports how many steps are done,
+/// to the `on_progress` callback if there is one, and otherwise to the progress bar.
+///
+/// A step is rendering or encoding a single frame.
+struct Progress<'a> {
+    /// The amount of steps in the whole render.
+    total: usize,
+    /// The amount of steps done so far.
+    done: std::sync::atomic::AtomicUsize,
+    /// The callback set with `Renderer::on_progress`.
+    callback: Option<&'a (dyn Fn(usize, usize) + Send + Sync)>,
+    /// The progress bar, shown when there is no callback.
+    #[cfg(feature = "progress")]
+    bar: Option<indicatif::ProgressBar>,
+}
+
+impl<'a> Progress<'a> {
+    /// Start reporting progress for `total` steps.
+    fn new(renderer: &'a Renderer, total: usize) -> Self {
+        let callback = renderer.on_progress.as_deref();
+        Self {
+            total,
+            done: std::sync::atomic::AtomicUsize::new(0),
+            callback,
+            #[cfg(feature = "progress")]
+            bar: callback
+                .is_none()
+                .then(|| indicatif::ProgressBar::new(total as u64)),
+        }
+    }
+
+    /// Mark `steps` more steps as done.
+    fn inc(&self, steps: usize) {
+        let done = self
+            .done
+            .fetch_add(steps, std::sync::atomic::Ordering::Relaxed)
+            + steps;
+        if let Some(callback) = self.callback {
+            callback(done, self.total);
+        }
+        #[cfg(feature = "progress")]
+        if let Some(bar) = &self.bar {
+            bar.inc(steps as u64);
+        }
+    }
+
+    /// Stop showing the progress bar.
+    fn finish(&self) {
+        #[cfg(feature = "progress")]
+        if let Some(bar) = &self.bar {
+            bar.finish();
+        }
+    }
+}
+
+/// Whether the cancel token is set.
+fn is_cancelled(
+    token: &Option<Arc<std::sync::atomic::AtomicBool>>,
+) -> bool {
+    token.as_ref().is_some_and(|token| {
+        token.load(std::sync::atomic::Ordering::Relaxed)
+    })
+}
+
+/// The message of a caught panic, if it has one.
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// The 64 bit FNV-1a hash of the bytes.
+///
+/// Used instead of `DefaultHasher` as its output is allowed to change between Rust versions.
+fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    /// The FNV offset basis.
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    /// The FNV prime.
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    bytes.into_iter().fold(OFFSET, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(PRIME)
+    })
 }
 
 /// Calculates and returns a iterator of all frame indexes between the start and end time.
@@ -200,13 +890,108 @@ fn frame_range(
     start: f32,
     end: f32,
     fps: usize,
-) -> impl Iterator<Item = usize> {
+) -> std::ops::Range<usize> {
     let frame_duration = 1.0 / fps as f32;
     let start_frame = (start / frame_duration).floor() as usize;
     let end_frame = (end / frame_duration).ceil() as usize;
     start_frame..end_frame
 }
 
+/// The amount of frames each rendering thread gets per batch.
+const FRAMES_PER_THREAD: usize = 4;
+
+/// How many rendered batches can wait for the encoder before rendering blocks.
+const QUEUED_BATCHES: usize = 1;
+
+/// The x264 preset, trading encoding speed for compression.
+///
+/// Slower presets give a smaller file at the same quality.
+/// For `OutputFormat::WebmVp9` they are mapped to the matching libvpx `cpu-used` speed.
+///
+/// see: https://trac.ffmpeg.org/wiki/Encode/H.264#Preset
+#[allow(missing_docs)] // Same as the x264 presets
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum EncoderPreset {
+    Ultrafast,
+    Superfast,
+    Veryfast,
+    Faster,
+    Fast,
+    #[default]
+    Medium,
+    Slow,
+    Slower,
+    Veryslow,
+}
+
+impl EncoderPreset {
+    /// The name x264 uses for the preset.
+    fn as_x264(self) -> &'static str {
+        match self {
+            Self::Ultrafast => "ultrafast",
+            Self::Superfast => "superfast",
+            Self::Veryfast => "veryfast",
+            Self::Faster => "faster",
+            Self::Fast => "fast",
+            Self::Medium => "medium",
+            Self::Slow => "slow",
+            Self::Slower => "slower",
+            Self::Veryslow => "veryslow",
+        }
+    }
+
+    /// The libvpx `cpu-used` speed closest to the preset, from 8 (fastest) to 0 (slowest).
+    fn as_vpx_speed(self) -> u8 {
+        8 - self as u8
+    }
+}
+
+/// The container and codec of the rendered video.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// H.264 in a MP4 file, plays almost everywhere.
+    ///
+    /// Transparent parts of the frame end up black.
+    #[default]
+    Mp4H264,
+    /// VP9 in a WebM file, smaller than H.264 at the same quality.
+    ///
+    /// Keeps the alpha channel, so with a transparent `background` the video can be
+    /// layered on top of other content.
+    /// Needs ffmpeg built with libvpx.
+    WebmVp9,
+}
+
+impl OutputFormat {
+    /// The file extension of the container.
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Mp4H264 => "mp4",
+            Self::WebmVp9 => "webm",
+        }
+    }
+}
+
+/// Where the point `(0, 0)` of the scene ends up in the frame.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum Origin {
+    /// `(0, 0)` is the middle of the frame, so positions range from minus half to plus half the size.
+    #[default]
+    Center,
+    /// `(0, 0)` is the top left corner of the frame, like in plain SVG.
+    TopLeft,
+}
+
+impl Origin {
+    /// The top left corner of an area of the given size.
+    fn corner(self, width: f32, height: f32) -> (f32, f32) {
+        match self {
+            Self::Center => (-width / 2.0, -height / 2.0),
+            Self::TopLeft => (0.0, 0.0),
+        }
+    }
+}
+
 /// The core renderer for the library.
 pub struct Renderer {
     /// The width of the video.
@@ -217,6 +1002,57 @@ pub struct Renderer {
     fps: u32,
     /// The timeline of the video.
     timeline: Timeline,
+    /// Whether to draw the timestamp and frame index on each frame.
+    debug_overlay: bool,
+    /// Whether `storyboard` writes the timestamp under each thumbnail.
+    storyboard_captions: bool,
+    /// Whether the GIF from `render_gif` loops forever.
+    gif_loop: bool,
+    /// The amount of threads the encoder uses, `None` uses the ffmpeg default.
+    encoder_threads: Option<usize>,
+    /// The x264 preset used to encode the video.
+    encoder_preset: EncoderPreset,
+    /// The constant rate factor of the video, `None` uses the x264 default.
+    crf: Option<u8>,
+    /// The target bitrate of the video in kbit/s, used instead of a constant quality.
+    bitrate: Option<u32>,
+    /// The container and codec of the video.
+    format: OutputFormat,
+    /// Each frame is rendered at this many times the resolution and scaled down.
+    supersample: u32,
+    /// Whether text is drawn as exact outlines, without legibility tweaks.
+    outline_fonts: bool,
+    /// Whether fonts are resolved from a database with a fixed face order.
+    deterministic_fonts: bool,
+    /// Font files registered with `load_font`.
+    fonts: resvg::usvg::fontdb::Database,
+    /// Whether the installed system fonts are used besides the loaded fonts.
+    system_fonts: bool,
+    /// The font database built from the font settings, shared by every frame.
+    ///
+    /// Cleared whenever a font setting changes.
+    font_cache:
+        std::sync::OnceLock<Arc<resvg::usvg::fontdb::Database>>,
+    /// Where the rendered video is written to.
+    output_path: std::path::PathBuf,
+    /// An audio file muxed into the rendered video, if set.
+    audio: Option<std::path::PathBuf>,
+    /// The color drawn behind all objects.
+    background: Color,
+    /// The `(start, end, color)` of each background fade, sorted by start time.
+    background_fades: Vec<(f32, f32, Color)>,
+    /// Only objects with a z-index in this range are drawn, if set.
+    isolated_z: Option<std::ops::Range<isize>>,
+    /// The `(x, y, width, height)` of the scene shown in the frame, if set.
+    view_box: Option<(f32, f32, f32, f32)>,
+    /// The `(width, height)` the scene is laid out for, if set with `reference_resolution`.
+    reference_size: Option<(f32, f32)>,
+    /// Where `(0, 0)` is placed when there is no view box.
+    origin: Origin,
+    /// Rendering stops early once this is set to `true`.
+    cancel: Option<Arc<std::sync::atomic::AtomicBool>>,
+    /// Called with `(steps_done, total_steps)` while rendering, replacing the progress bar.
+    on_progress: Option<Box<dyn Fn(usize, usize) + Send + Sync>>,
 }
 
 impl Renderer {
@@ -227,6 +1063,30 @@ impl Renderer {
             height,
             fps: 60,
             timeline: Default::default(),
+            debug_overlay: false,
+            storyboard_captions: true,
+            gif_loop: true,
+            encoder_threads: None,
+            encoder_preset: EncoderPreset::default(),
+            crf: None,
+            bitrate: None,
+            format: OutputFormat::default(),
+            supersample: 1,
+            outline_fonts: false,
+            deterministic_fonts: false,
+            fonts: resvg::usvg::fontdb::Database::new(),
+            system_fonts: true,
+            font_cache: std::sync::OnceLock::new(),
+            output_path: "output.mp4".into(),
+            audio: None,
+            background: Color(0, 0, 0, 0),
+            background_fades: Vec::new(),
+            isolated_z: None,
+            view_box: None,
+            reference_size: None,
+            origin: Origin::default(),
+            cancel: None,
+            on_progress: None,
         }
     }
 
@@ -238,74 +1098,961 @@ impl Renderer {
         self
     }
 
-    /// Gets a reference to the timeline, which is used to add objects and animations.
-    pub fn timeline(&mut self) -> &mut Timeline {
-        &mut self.timeline
+    /// Draw the timestamp and frame index in the top left corner of each frame.
+    ///
+    /// Useful for debugging the timing of animations.
+    pub fn debug_overlay(&mut self, enabled: bool) -> &mut Self {
+        self.debug_overlay = enabled;
+        self
     }
 
-    /// Render the video and return the output location.
-    pub fn render(self) -> RenderingResult {
-        log::info!("Initing rendering runtime");
+    /// Whether `storyboard` captions each thumbnail with its timestamp.
+    ///
+    /// Enabled by default.
+    pub fn storyboard_captions(
+        &mut self,
+        enabled: bool,
+    ) -> &mut Self {
+        self.storyboard_captions = enabled;
+        self
+    }
 
-        let output_location = std::path::Path::new("output.mp4");
+    /// Whether the GIF from `render_gif` loops forever, instead of playing once.
+    ///
+    /// Enabled by default.
+    pub fn gif_loop(&mut self, enabled: bool) -> &mut Self {
+        self.gif_loop = enabled;
+        self
+    }
 
-        video_rs::init().unwrap();
-        let settings =
-            video_rs::encode::Settings::preset_h264_yuv420p(
-                self.width,
-                self.height,
-                false,
+    /// Sets the amount of threads ffmpeg uses to encode the video.
+    ///
+    /// `0` lets ffmpeg pick based on the amount of cores.
+    pub fn encoder_threads(&mut self, threads: usize) -> &mut Self {
+        self.encoder_threads = Some(threads);
+        self
+    }
+
+    /// Sets the x264 preset used to encode the video.
+    ///
+    /// Defaults to `EncoderPreset::Medium`.
+    pub fn encoder_preset(
+        &mut self,
+        preset: EncoderPreset,
+    ) -> &mut Self {
+        self.encoder_preset = preset;
+        self
+    }
+
+    /// Encodes the video at a constant quality, given as a crf value.
+    ///
+    /// Lower is better.
+    /// For H.264 `0` is lossless, x264 uses `23` by default and values above `51` are clamped.
+    /// For VP9 the scale goes up to `63`, and `31` is used by default.
+    /// This replaces a bitrate set with `bitrate`.
+    pub fn quality(&mut self, crf: u8) -> &mut Self {
+        self.crf = Some(crf.min(63));
+        self.bitrate = None;
+        self
+    }
+
+    /// Encodes the video at an average bitrate in kbit/s.
+    ///
+    /// This replaces a quality set with `quality`.
+    pub fn bitrate(&mut self, kbps: u32) -> &mut Self {
+        self.bitrate = Some(kbps);
+        self.crf = None;
+        self
+    }
+
+    /// Sets the container and codec of the rendered video.
+    ///
+    /// Defaults to `OutputFormat::Mp4H264`.
+    /// The extension of the output path is replaced with the one of the format,
+    /// `RenderingResult::output_location` has the actual path.
+    pub fn format(&mut self, format: OutputFormat) -> &mut Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets where the rendered video is written to.
+    ///
+    /// Defaults to `output.mp4` in the current directory.
+    /// The extension is replaced to match the `format`.
+    pub fn output_path(
+        &mut self,
+        path: impl Into<std::path::PathBuf>,
+    ) -> &mut Self {
+        self.output_path = path.into();
+        self
+    }
+
+    /// Add the audio of the given file to the rendered video.
+    ///
+    /// The audio is copied into the video without re-encoding,
+    /// so it has to be in a codec the output container supports,
+    /// like AAC or MP3 for MP4 and Opus or Vorbis for WebM.
+    /// Audio running past the end of the video is cut off,
+    /// and shorter audio just leaves the rest of the video silent.
+    pub fn audio(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> &mut Self {
+        self.audio = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets the color drawn behind all objects.
+    ///
+    /// Defaults to transparent, which ends up black in the video.
+    pub fn background(&mut self, color: Color) -> &mut Self {
+        self.background = color;
+        self
+    }
+
+    /// Fade the background to `color` over `duration` seconds, starting at `start`.
+    ///
+    /// Each fade starts from the color the background has at its start,
+    /// so fades can be chained to change the mood of several scenes.
+    pub fn fade_background(
+        &mut self,
+        color: Color,
+        start: f32,
+        duration: f32,
+    ) -> &mut Self {
+        self.background_fades.push((start, start + duration, color));
+        self.background_fades.sort_by(|a, b| a.0.total_cmp(&b.0));
+        self
+    }
+
+    /// The background color at the given time, with all fades applied.
+    fn background_at(&self, time: f32) -> Color {
+        let mut color = self.background;
+        for (start, end, target) in &self.background_fades {
+            if time < *start {
+                break;
+            }
+            let progress = if end > start {
+                ((time - start) / (end - start)).min(1.0)
+            } else {
+                1.0
+            };
+            color = color.morph(target, progress);
+        }
+        color
+    }
+
+    /// Render each frame at `factor` times the resolution and scale it down,
+    /// which smooths out jagged edges.
+    ///
+    /// Rendering time grows with the square of the factor, defaults to 1 (no supersampling).
+    pub fn supersample(&mut self, factor: u32) -> &mut Self {
+        self.supersample = factor.max(1);
+        self
+    }
+
+    /// Draw text as the exact glyph outlines,
+    /// so text looks the same no matter its size or position.
+    pub fn outline_fonts(&mut self, enabled: bool) -> &mut Self {
+        self.outline_fonts = enabled;
+        self
+    }
+
+    /// Resolve fonts from the system fonts sorted by family and file,
+    /// rather than in the order the system lists them.
+    ///
+    /// When several installed fonts match a request the same one is picked on every machine that has it.
+    pub fn deterministic_fonts(
+        &mut self,
+        enabled: bool,
+    ) -> &mut Self {
+        self.deterministic_fonts = enabled;
+        self.font_cache.take();
+        self
+    }
+
+    /// Load a TTF/OTF font file (or collection) to use when rendering text.
+    ///
+    /// Loaded fonts take priority over system fonts with the same family name.
+    /// Animations that turn text into outlines when they are created, like `TextWrite`,
+    /// only see the system fonts.
+    pub fn load_font(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> std::io::Result<&mut Self> {
+        self.fonts.load_font_file(path)?;
+        self.font_cache.take();
+        Ok(self)
+    }
+
+    /// Whether to use the installed system fonts besides the ones from `load_font`.
+    ///
+    /// Disabling them makes text render the same on every machine.
+    /// Defaults to `true`.
+    pub fn system_fonts(&mut self, enabled: bool) -> &mut Self {
+        self.system_fonts = enabled;
+        self.font_cache.take();
+        self
+    }
+
+    /// Only draw objects and animations with a z-index in the given range.
+    ///
+    /// Useful to find out which layer a stray element is on.
+    /// The background and the debug overlay are always drawn.
+    pub fn isolate_z(
+        &mut self,
+        range: std::ops::Range<isize>,
+    ) -> &mut Self {
+        self.isolated_z = Some(range);
+        self
+    }
+
+    /// Set the part of the scene shown in the frame, independent of the output resolution.
+    ///
+    /// The area is scaled to fit the width and height of the video,
+    /// if the aspect ratios differ it is centered with empty space around it.
+    /// Defaults to the size of the video in pixels, placed according to `origin`.
+    pub fn view_box(
+        &mut self,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    ) -> &mut Self {
+        self.view_box = Some((x, y, width, height));
+        self
+    }
+
+    /// Lay out the scene for the given resolution, whatever the actual output size.
+    ///
+    /// Positions, sizes and stroke widths are all in pixels of the reference resolution
+    /// and scaled to the output, so the scene keeps the same visual weight when exported
+    /// at a different size.
+    /// A shorthand for a `view_box` of that size, placed according to `origin`
+    /// whether it is set before or after this.
+    pub fn reference_resolution(
+        &mut self,
+        width: usize,
+        height: usize,
+    ) -> &mut Self {
+        self.reference_size = Some((width as f32, height as f32));
+        self.view_box = None;
+        self
+    }
+
+    /// Sets where `(0, 0)` of the scene is placed in the frame.
+    ///
+    /// Defaults to `Origin::Center`, so an object at `(0, 0)` shows up in the middle of the video
+    /// and the corners are at plus or minus half the width and height.
+    /// `Origin::TopLeft` puts `(0, 0)` in the top left corner instead, like in plain SVG.
+    /// In both cases y points down.
+    /// Has no effect when a `view_box` is set, as that already decides what is shown.
+    pub fn origin(&mut self, origin: Origin) -> &mut Self {
+        self.origin = origin;
+        self
+    }
+
+    /// The `(x, y, width, height)` of the scene shown in the frame.
+    fn view_rect(&self) -> (f32, f32, f32, f32) {
+        self.view_box.unwrap_or_else(|| {
+            let (width, height) = self
+                .reference_size
+                .unwrap_or((self.width as f32, self.height as f32));
+            let (x, y) = self.origin.corner(width, height);
+            (x, y, width, height)
+        })
+    }
+
+    /// Stop rendering once the token is set to `true`, for example from another thread.
+    ///
+    /// `render` then finishes the video with the frames encoded so far,
+    /// and returns normally.
+    pub fn cancel_token(
+        &mut self,
+        token: Arc<std::sync::atomic::AtomicBool>,
+    ) -> &mut Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Call `callback` with `(steps_done, total_steps)` as frames finish,
+    /// instead of showing the progress bar.
+    ///
+    /// `render` and `render_gif` count two steps for each frame,
+    /// one when it is rendered and one when it is encoded,
+    /// so `total_steps` is twice the frame count.
+    /// `render_frames_to_dir` writes each frame as soon as it is rendered, so it counts one step each.
+    /// The callback is called from the rendering threads, so it should return quickly.
+    pub fn on_progress(
+        &mut self,
+        callback: impl Fn(usize, usize) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.on_progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Turn on all the settings for high quality, reproducible output.
+    ///
+    /// Enables `outline_fonts`, `deterministic_fonts` and 2x `supersample`.
+    pub fn high_fidelity(&mut self) -> &mut Self {
+        self.outline_fonts(true)
+            .deterministic_fonts(true)
+            .supersample(2)
+    }
+
+    /// Gets a reference to the timeline, which is used to add objects and animations.
+    pub fn timeline(&mut self) -> &mut Timeline {
+        &mut self.timeline
+    }
+
+    /// The amount of frames `render` will produce at the current frame rate.
+    pub fn frame_count(&self) -> usize {
+        self.timeline.frame_count(self.fps as usize)
+    }
+
+    /// Write a WebVTT chapters file with a chapter for each labeled animation.
+    ///
+    /// Each chapter lasts until the start of the next one,
+    /// and the last one until the end of the video.
+    pub fn export_chapters(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> std::io::Result<()> {
+        let chapters = self.timeline.chapters();
+        let end_time = self.timeline.duration();
+
+        let mut output = String::from("WEBVTT\n");
+        for (index, (start, label)) in chapters.iter().enumerate() {
+            let end = chapters
+                .get(index + 1)
+                .map_or(end_time, |(next_start, _)| *next_start);
+            output.push_str(&format!(
+                "\n{}\n{} --> {}\n{}\n",
+                index + 1,
+                format_vtt_time(*start),
+                format_vtt_time(end.max(*start)),
+                label
+            ));
+        }
+
+        std::fs::write(path, output)
+    }
+
+    /// Resolve the font of every text in the timeline, without rendering the video.
+    ///
+    /// Each text span reports the font file usvg picked for it,
+    /// which is useful to confirm a requested font is installed.
+    /// Math is rendered as paths by MathJax, so it never shows up here.
+    /// Fails if any of the objects renders SVG usvg can't parse.
+    pub fn resolved_fonts(
+        &self,
+    ) -> Result<Vec<ResolvedFont>, RenderError> {
+        let fonts = self.font_database();
+
+        let nodes = self
+            .timeline
+            .objects
+            .iter()
+            .map(|(_, node)| node.clone())
+            .chain(
+                self.timeline
+                    .animations
+                    .iter()
+                    .map(|animated| animated.object.render().1),
             );
-        let mut encoder =
-            video_rs::encode::Encoder::new(output_location, settings)
-                .unwrap();
 
-        let mut video_position = Time::zero();
-        let frame_duration = Time::from_secs(1.0 / self.fps as f32);
+        let mut resolved = Vec::new();
+        for node in nodes {
+            let doc = svg::Document::new().add(node).to_string();
+            let tree = resvg::usvg::Tree::from_str(
+                &doc,
+                &Default::default(),
+                &fonts,
+            )
+            .map_err(RenderError::Svg)?;
+            collect_fonts(tree.root(), &fonts, &mut resolved);
+        }
+
+        for font in &resolved {
+            log::info!(
+                "{:?} requested {:?}, resolved to {:?} ({:?})",
+                font.text,
+                font.requested,
+                font.family,
+                font.path
+            );
+        }
+        Ok(resolved)
+    }
+
+    /// Render every frame to a PNG file in `dir`, instead of encoding a video.
+    ///
+    /// The files are named after the frame index (starting at 0), like `frame_00000.png`,
+    /// zero-padded so they sort in order.
+    /// The directory is created if it doesnt exist.
+    pub fn render_frames_to_dir(
+        self,
+        dir: impl AsRef<std::path::Path>,
+    ) -> Result<(), RenderError> {
+        /// The minimum amount of digits in the file names.
+        const MIN_DIGITS: usize = 5;
+
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir).map_err(|source| {
+            RenderError::Io {
+                path: dir.to_path_buf(),
+                source,
+            }
+        })?;
 
         log::info!("Calculating timeline/frames");
         let frames = self.timeline.calc_frames(self.fps as usize);
+        let fonts = self.font_database();
+        let digits = frames.len().to_string().len().max(MIN_DIGITS);
 
         log::info!("Rendering frames");
-        let frames_count = frames.len();
-        let frames = frames.into_par_iter();
-        #[cfg(feature = "progress")]
-        let frames = frames.progress_count(frames_count as u64);
-        let frames = frames
-            .panic_fuse()
+        let progress = Progress::new(&self, frames.len());
+
+        frames.into_par_iter().panic_fuse().try_for_each(
+            |frame| {
+                let path = dir.join(format!(
+                    "frame_{:0digits$}.png",
+                    frame.index
+                ));
+                let doc = self.render_frame(frame);
+                let png =
+                    self.encode_png(&self.render_svg(doc, &fonts)?)?;
+                std::fs::write(&path, png).map_err(|source| {
+                    RenderError::Io { path, source }
+                })?;
+
+                progress.inc(1);
+                Ok(())
+            },
+        )?;
+
+        progress.finish();
+        log::info!("Rendering complete");
+        Ok(())
+    }
+
+    /// Render the video as an animated GIF at `path`, instead of encoding a video.
+    ///
+    /// Each frame is reduced to its own 256 color palette.
+    /// GIF delays are in hundredths of a second, so at frame rates that dont divide 100
+    /// the delays alternate to keep the overall timing right.
+    pub fn render_gif(
+        self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), RenderError> {
+        /// How hard the palette quantization works, from 1 (best) to 30 (fastest).
+        const QUANTIZE_SPEED: i32 = 10;
+
+        let path = path.as_ref();
+        let io_error = |source| RenderError::Io {
+            path: path.to_path_buf(),
+            source,
+        };
+        let (Ok(width), Ok(height)) =
+            (u16::try_from(self.width), u16::try_from(self.height))
+        else {
+            return Err(RenderError::Gif(gif::EncodingError::Io(
+                std::io::Error::other(
+                    "gifs can be at most 65535 pixels wide and high",
+                ),
+            )));
+        };
+
+        let file = std::fs::File::create(path).map_err(io_error)?;
+        let mut encoder = gif::Encoder::new(
+            std::io::BufWriter::new(file),
+            width,
+            height,
+            &[],
+        )
+        .map_err(RenderError::Gif)?;
+        if self.gif_loop {
+            encoder
+                .set_repeat(gif::Repeat::Infinite)
+                .map_err(RenderError::Gif)?;
+        }
+
+        log::info!("Calculating timeline/frames");
+        let frames = self.timeline.calc_frames(self.fps as usize);
+        let fonts = self.font_database();
+        // The time a frame starts at, in hundredths of a second.
+        let centiseconds = |index: usize| {
+            (index as f32 * 100.0 / self.fps as f32).round()
+        };
+
+        log::info!("Rendering frames");
+        let progress = Progress::new(&self, 2 * frames.len());
+
+        let batch_size =
+            rayon::current_num_threads() * FRAMES_PER_THREAD;
+        let mut frames = frames.into_iter();
+        loop {
+            let batch =
+                frames.by_ref().take(batch_size).collect::<Vec<_>>();
+            if batch.is_empty() || is_cancelled(&self.cancel) {
+                break;
+            }
+
+            let batch = batch
+                .into_par_iter()
+                .panic_fuse()
+                .map(|frame| {
+                    let index = frame.index;
+                    let doc = self.render_frame(frame);
+                    let image = self.render_svg(doc, &fonts)?;
+                    let mut frame = gif::Frame::from_rgb_speed(
+                        width,
+                        height,
+                        &image.iter().copied().collect::<Vec<_>>(),
+                        QUANTIZE_SPEED,
+                    );
+                    frame.delay = (centiseconds(index + 1)
+                        - centiseconds(index))
+                        as u16;
+                    progress.inc(1);
+                    Ok(frame)
+                })
+                .collect::<Result<Vec<_>, RenderError>>()?;
+
+            for frame in &batch {
+                encoder
+                    .write_frame(frame)
+                    .map_err(RenderError::Gif)?;
+            }
+            progress.inc(batch.len());
+        }
+
+        let mut writer = encoder.into_inner().map_err(io_error)?;
+        std::io::Write::flush(&mut writer).map_err(io_error)?;
+
+        progress.finish();
+        log::info!("Rendering complete");
+        Ok(())
+    }
+
+    /// Render the frame closest to `time` (in seconds) and return it as PNG bytes.
+    ///
+    /// The time is snapped to the nearest frame, so the result is exactly the frame
+    /// `render` would put in the video at that point.
+    /// Only the animations and objects active at that frame are resolved.
+    pub fn render_single_frame(
+        &self,
+        time: f32,
+    ) -> Result<Vec<u8>, RenderError> {
+        let frame = self.timeline.frame_at(time, self.fps as usize);
+        let doc = self.render_frame(frame);
+        self.encode_png(&self.render_svg(doc, &self.font_database())?)
+    }
+
+    /// Render a grid of thumbnails sampled evenly across the video into a single PNG.
+    ///
+    /// Each of the `rows * cols` thumbnails shows the middle of its slice of the video,
+    /// the sheet as a whole has the size of one frame.
+    /// Useful for reviewing the pacing of a video without watching it.
+    /// Returns `RenderError::EmptyStoryboard` if `rows` or `cols` is zero.
+    pub fn storyboard(
+        &self,
+        rows: usize,
+        cols: usize,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), RenderError> {
+        /// The height of the captions relative to the height of a thumbnail.
+        const CAPTION_SIZE: f32 = 0.1;
+
+        if rows == 0 || cols == 0 {
+            return Err(RenderError::EmptyStoryboard { rows, cols });
+        }
+        let path = path.as_ref();
+        let count = rows * cols;
+        let end_time = self.timeline.duration();
+        let (width, height) = (self.width as f32, self.height as f32);
+
+        log::info!("Rendering storyboard");
+        let thumbnails = (0..count)
+            .into_par_iter()
+            .map(|index| {
+                let time =
+                    end_time * (index as f32 + 0.5) / count as f32;
+                Ok((time, self.render_single_frame(time)?))
+            })
+            .collect::<Result<Vec<_>, RenderError>>()?;
+
+        let mut sheet = svg::Document::new()
+            .set("width", self.width)
+            .set("height", self.height)
+            .set(
+                "viewBox",
+                (0, 0, width * cols as f32, height * rows as f32),
+            );
+        for (index, (time, png)) in thumbnails.into_iter().enumerate()
+        {
+            let x = (index % cols) as f32 * width;
+            let y = (index / cols) as f32 * height;
+            let thumbnail =
+                objects::Image::from_bytes(&png, "image/png")
+                    .at(x, y)
+                    .size(width, height);
+            sheet = sheet.add(objects::Object::render(&thumbnail).1);
+
+            if self.storyboard_captions {
+                let size = height * CAPTION_SIZE;
+                let backdrop =
+                    objects::Rectangle::new(width, size * 1.5)
+                        .at(x, y + height - size * 1.5)
+                        .fill(Color(0, 0, 0, 160))
+                        .outline(Color(0, 0, 0, 0));
+                let caption =
+                    objects::Text::new(format!("{:.2}s", time))
+                        .anchor("start")
+                        .size(size)
+                        .at(x + size / 2.0, y + height - size / 2.0);
+                sheet = sheet
+                    .add(objects::Object::render(&backdrop).1)
+                    .add(objects::Object::render(&caption).1);
+            }
+        }
+
+        let tree = resvg::usvg::Tree::from_str(
+            &sheet.to_string(),
+            &Default::default(),
+            &self.font_database(),
+        )
+        .map_err(RenderError::Svg)?;
+        let mut pixmap = resvg::tiny_skia::Pixmap::new(
+            self.width as u32,
+            self.height as u32,
+        )
+        .ok_or(RenderError::Pixmap {
+            width: self.width as u32,
+            height: self.height as u32,
+        })?;
+        resvg::render(
+            &tree,
+            resvg::tiny_skia::Transform::default(),
+            &mut pixmap.as_mut(),
+        );
+
+        let png = pixmap.encode_png().map_err(|error| {
+            RenderError::Png(std::io::Error::other(error))
+        })?;
+        std::fs::write(path, png).map_err(|source| RenderError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Render every frame and return a hash of each frame's pixels.
+    ///
+    /// The hashes are stable across runs,
+    /// so they can be committed and compared in CI to catch unintended rendering changes
+    /// without storing the frames themselves.
+    ///
+    /// Text is drawn with whatever fonts are installed unless `system_fonts(false)` is set,
+    /// so to get the same hashes on other machines load the fonts with `load_font`
+    /// and disable the system fonts.
+    pub fn frame_hashes(&self) -> Result<Vec<u64>, RenderError> {
+        let frames = self.timeline.calc_frames(self.fps as usize);
+        let fonts = self.font_database();
+
+        frames
+            .into_par_iter()
             .map(|frame| {
                 let doc = self.render_frame(frame);
-                self.render_svg(doc)
+                let image = self.render_svg(doc, &fonts)?;
+                Ok(fnv1a(image.iter().copied()))
             })
-            .collect::<Vec<_>>();
+            .collect()
+    }
 
-        log::info!("Encoding frames");
-        let frames = frames.into_iter();
-        #[cfg(feature = "progress")]
-        let frames = frames.progress();
-        for frame in frames {
-            encoder.encode(&frame, &video_position).unwrap();
-            video_position =
-                video_position.aligned_with(&frame_duration).add();
+    /// Encode a rendered rgb frame as a PNG.
+    fn encode_png(
+        &self,
+        image: &ndarray::Array3<u8>,
+    ) -> Result<Vec<u8>, RenderError> {
+        let mut pixmap = resvg::tiny_skia::Pixmap::new(
+            self.width as u32,
+            self.height as u32,
+        )
+        .ok_or(RenderError::Pixmap {
+            width: self.width as u32,
+            height: self.height as u32,
+        })?;
+        for (pixel, rgb) in
+            pixmap.pixels_mut().iter_mut().zip(image.rows())
+        {
+            *pixel = resvg::tiny_skia::ColorU8::from_rgba(
+                rgb[0], rgb[1], rgb[2], 255,
+            )
+            .premultiply();
         }
 
-        log::info!("Finishing encoding");
-        encoder.finish().unwrap();
+        pixmap.encode_png().map_err(|error| {
+            RenderError::Png(std::io::Error::other(error))
+        })
+    }
+
+    /// Render the video and return the output location.
+    pub fn render(self) -> Result<RenderingResult, RenderError> {
+        log::info!("Initing rendering runtime");
 
-        log::info!("Rendering complete");
+        let extension = self.format.extension();
+        let output_location =
+            self.output_path.with_extension(extension);
+        let output_location = output_location.as_path();
+        // With audio the video is encoded on its own first, and muxed with the audio afterwards.
+        let video_location = match self.audio {
+            Some(_) => output_location
+                .with_extension(format!("video.{extension}")),
+            None => output_location.to_path_buf(),
+        };
+
+        init_video()?;
+        let mut encoder = self.video_encoder(&video_location)?;
+        let _temporary_video = self
+            .audio
+            .is_some()
+            .then(|| TemporaryFile(video_location.clone()));
+        let alpha = self.format == OutputFormat::WebmVp9;
+
+        let mut video_position = Time::zero();
+        let frame_duration = Time::from_secs(1.0 / self.fps as f32);
+
+        log::info!("Calculating timeline/frames");
+        let frames = self.timeline.calc_frames(self.fps as usize);
+        let fonts = self.font_database();
+
+        log::info!("Rendering and encoding frames");
+        let progress = Progress::new(&self, 2 * frames.len());
 
-        RenderingResult {
+        // Frames are rendered in batches and handed to the encoder as they complete,
+        // so only a few batches are ever in memory at once.
+        let batch_size =
+            rayon::current_num_threads() * FRAMES_PER_THREAD;
+        let (sender, receiver) =
+            std::sync::mpsc::sync_channel(QUEUED_BATCHES);
+        let cancel = &self.cancel;
+        let progress = &progress;
+
+        let frames_encoded = std::thread::scope(|scope| {
+            let encoding = scope.spawn(move || {
+                let mut frame_index = 0;
+                'batches: for batch in receiver {
+                    for frame in batch {
+                        if is_cancelled(cancel) {
+                            break 'batches;
+                        }
+                        encoder
+                            .encode(&frame, &video_position)
+                            .map_err(|source| {
+                                RenderError::Encode {
+                                    frame: frame_index,
+                                    source,
+                                }
+                            })?;
+                        video_position = video_position
+                            .aligned_with(&frame_duration)
+                            .add();
+                        frame_index += 1;
+                        progress.inc(1);
+                    }
+                }
+
+                log::info!("Finishing encoding");
+                encoder.finish().map_err(RenderError::Finish)?;
+                Ok(frame_index)
+            });
+
+            // Sending only fails if the encoder stopped because of an error.
+            let rendering = self.render_batches(
+                frames,
+                batch_size,
+                &fonts,
+                alpha,
+                progress,
+                |batch| sender.send(batch).is_ok(),
+            );
+            drop(sender);
+
+            let encoding = encoding.join().unwrap_or_else(|panic| {
+                Err(RenderError::EncoderPanicked(panic_message(
+                    &*panic,
+                )))
+            });
+            rendering.and(encoding)
+        })?;
+
+        if let Some(audio) = &self.audio {
+            log::info!("Adding audio");
+            let duration = frames_encoded as f64 / self.fps as f64;
+            mux_audio(
+                &video_location,
+                audio,
+                output_location,
+                duration,
+            )
+            .map_err(RenderError::Mux)?;
+        }
+
+        progress.finish();
+        if is_cancelled(&self.cancel) {
+            log::info!("Rendering cancelled");
+        } else {
+            log::info!("Rendering complete");
+        }
+
+        Ok(RenderingResult {
             output_location: output_location.into(),
+        })
+    }
+
+    /// Render the frames in batches of `batch_size`, handing each batch to `send` in order.
+    ///
+    /// Stops early when rendering is cancelled, or when `send` returns false.
+    fn render_batches(
+        &self,
+        frames: Vec<Frame>,
+        batch_size: usize,
+        fonts: &resvg::usvg::fontdb::Database,
+        alpha: bool,
+        progress: &Progress,
+        mut send: impl FnMut(Vec<ndarray::Array3<u8>>) -> bool,
+    ) -> Result<(), RenderError> {
+        let cancel = &self.cancel;
+        let mut frames = frames.into_iter();
+        loop {
+            let batch =
+                frames.by_ref().take(batch_size).collect::<Vec<_>>();
+            if batch.is_empty() || is_cancelled(cancel) {
+                return Ok(());
+            }
+
+            let batch = batch
+                .into_par_iter()
+                .panic_fuse()
+                .map(|frame| {
+                    if is_cancelled(cancel) {
+                        return Ok(None);
+                    }
+                    let doc = self.render_frame(frame);
+                    let image = self.rasterize(doc, fonts, alpha)?;
+                    progress.inc(1);
+                    Ok(Some(image))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            // Frames skipped because of a cancel leave a gap, so only keep the ones before it.
+            let batch = batch
+                .into_iter()
+                .map_while(|frame| frame)
+                .collect::<Vec<_>>();
+
+            if !send(batch) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Create the encoder for the output format, writing to `path`.
+    fn video_encoder(
+        &self,
+        path: &std::path::Path,
+    ) -> Result<VideoEncoder, RenderError> {
+        let options = self.encoder_options();
+        match self.format {
+            OutputFormat::Mp4H264 => {
+                let settings =
+                    video_rs::encode::Settings::preset_h264_custom(
+                        self.width,
+                        self.height,
+                        video_rs::frame::PixelFormat::YUV420P,
+                        options.into(),
+                    );
+                video_rs::encode::Encoder::new(path, settings)
+                    .map(VideoEncoder::H264)
+                    .map_err(RenderError::CreateEncoder)
+            }
+            OutputFormat::WebmVp9 => Vp9Encoder::new(
+                path,
+                self.width,
+                self.height,
+                self.fps,
+                &options,
+            )
+            .map(VideoEncoder::Vp9)
+            .map_err(|error| {
+                RenderError::CreateEncoder(error.into())
+            }),
+        }
+    }
+
+    /// The codec options for the output format.
+    fn encoder_options(
+        &self,
+    ) -> std::collections::HashMap<String, String> {
+        let mut options = std::collections::HashMap::new();
+        match self.format {
+            OutputFormat::Mp4H264 => {
+                // Without any settings this matches `Settings::preset_h264_yuv420p`.
+                options.insert(
+                    "preset".to_string(),
+                    self.encoder_preset.as_x264().to_string(),
+                );
+                if let Some(crf) = self.crf {
+                    options.insert(
+                        "crf".to_string(),
+                        crf.min(51).to_string(),
+                    );
+                }
+            }
+            OutputFormat::WebmVp9 => {
+                options.insert(
+                    "cpu-used".to_string(),
+                    self.encoder_preset.as_vpx_speed().to_string(),
+                );
+                options.insert("row-mt".to_string(), "1".to_string());
+                // libvpx only uses a constant quality when the bitrate is 0.
+                if self.bitrate.is_none() {
+                    let crf = self.crf.unwrap_or(31);
+                    options
+                        .insert("crf".to_string(), crf.to_string());
+                    options.insert("b".to_string(), "0".to_string());
+                }
+            }
+        }
+        if let Some(kbps) = self.bitrate {
+            options.insert("b".to_string(), format!("{kbps}k"));
+        }
+        if let Some(threads) = self.encoder_threads {
+            options
+                .insert("threads".to_string(), threads.to_string());
         }
+        options
     }
 
     /// Render a single frame to a SVG document.
     fn render_frame(&self, frame: Frame) -> svg::node::element::SVG {
+        let (x, y, width, height) = self.view_rect();
         let mut doc = svg::Document::new()
-            .set("viewBox", (0, 0, self.width, self.height))
+            .set("viewBox", (x, y, width, height))
             .set("width", self.width)
             .set("height", self.height);
 
+        let background = self.background_at(frame.time);
+        if background.a() != 0 {
+            doc = doc.add(
+                svg::node::element::Rectangle::new()
+                    .set("x", x)
+                    .set("y", y)
+                    .set("width", width)
+                    .set("height", height)
+                    .set("fill", background.as_css().as_ref()),
+            );
+        }
+
         let mut objects = frame.objects;
 
         for animation in frame.animations {
@@ -313,6 +2060,27 @@ impl Renderer {
             objects.push(animation);
         }
 
+        if let Some(range) = &self.isolated_z {
+            objects.retain(|(z, _)| range.contains(z));
+        }
+
+        if self.debug_overlay {
+            /// The font size of the debug overlay in pixels.
+            const OVERLAY_SIZE: f32 = 40.0;
+
+            // Keep the overlay the same size in pixels, whatever the view box.
+            let size = OVERLAY_SIZE * width / self.width as f32;
+            let overlay = objects::Text::new(format!(
+                "{:.3}s | frame {}",
+                frame.time, frame.index
+            ))
+            .anchor("start")
+            .size(size)
+            .at(x + size / 2.0, y + size * 1.5)
+            .z_index(isize::MAX);
+            objects.push(objects::Object::render(&overlay));
+        }
+
         objects.sort_by_key(|(z, _)| *z);
         for (_, object) in objects {
             doc = doc.add(object);
@@ -321,45 +2089,1016 @@ impl Renderer {
         doc
     }
 
-    /// Render a SVG document to a pixel buffer.
+    /// The font database text is rendered with.
+    ///
+    /// The loaded fonts come first, followed by the system fonts if enabled.
+    /// Built on first use and reused until a font setting changes.
+    fn font_database(&self) -> Arc<resvg::usvg::fontdb::Database> {
+        self.font_cache
+            .get_or_init(|| {
+                let mut fonts = self.fonts.clone();
+                if self.system_fonts {
+                    let system = if self.deterministic_fonts {
+                        deterministic_font_database()
+                    } else {
+                        system_font_database()
+                    };
+                    for face in system.faces() {
+                        fonts.push_face_info(face.clone());
+                    }
+                }
+                Arc::new(fonts)
+            })
+            .clone()
+    }
+
+    /// Render a SVG document to a RGB pixel buffer.
     fn render_svg(
         &self,
         doc: svg::node::element::SVG,
-    ) -> ndarray::prelude::ArrayBase<
-        ndarray::OwnedRepr<u8>,
-        ndarray::prelude::Dim<[usize; 3]>,
-    > {
-        let node = convert_to_resvg(doc.to_string());
-        let mut pixel_map = resvg::tiny_skia::Pixmap::new(
-            self.width as u32,
-            self.height as u32,
+        fonts: &resvg::usvg::fontdb::Database,
+    ) -> Result<ndarray::Array3<u8>, RenderError> {
+        self.rasterize(doc, fonts, false)
+    }
+
+    /// Render a SVG document to a pixel buffer.
+    ///
+    /// With `alpha` the buffer is premultiplied RGBA,
+    /// otherwise it is RGB composited over the frame backdrop.
+    fn rasterize(
+        &self,
+        doc: svg::node::element::SVG,
+        fonts: &resvg::usvg::fontdb::Database,
+        alpha: bool,
+    ) -> Result<ndarray::Array3<u8>, RenderError> {
+        let mut options = resvg::usvg::Options::default();
+        if self.outline_fonts {
+            options.text_rendering =
+                resvg::usvg::TextRendering::GeometricPrecision;
+        }
+        let node = resvg::usvg::Tree::from_str(
+            &doc.to_string(),
+            &options,
+            fonts,
         )
-        .unwrap();
-        resvg::render(
-            &node,
-            resvg::tiny_skia::Transform::from_translate(
-                self.width as f32 / 2.0,
-                self.height as f32 / 2.0,
+        .map_err(RenderError::Svg)?;
+
+        let factor = self.supersample as usize;
+        let frame = with_pixmap(
+            (self.width * factor) as u32,
+            (self.height * factor) as u32,
+            |pixel_map| {
+                resvg::render(
+                    &node,
+                    resvg::tiny_skia::Transform::from_scale(
+                        factor as f32,
+                        factor as f32,
+                    ),
+                    &mut pixel_map.as_mut(),
+                );
+                if alpha {
+                    pixmap_to_rgba(pixel_map)
+                } else {
+                    pixmap_to_rgb(pixel_map, FRAME_BACKDROP)
+                }
+            },
+        )?;
+        if factor == 1 {
+            return Ok(frame);
+        }
+
+        // Average each block of `factor * factor` pixels.
+        Ok(ndarray::Array3::from_shape_fn(
+            (self.height, self.width, frame.dim().2),
+            |(y, x, channel)| {
+                let mut total = 0;
+                for dy in 0..factor {
+                    for dx in 0..factor {
+                        total += frame[[
+                            y * factor + dy,
+                            x * factor + dx,
+                            channel,
+                        ]] as usize;
+                    }
+                }
+                (total / (factor * factor)) as u8
+            },
+        ))
+    }
+}
+
+/// The color transparent parts of a frame are composited over,
+/// as the video itself has no alpha channel.
+const FRAME_BACKDROP: Color = Color::rgb(0, 0, 0);
+
+/// Copy the premultiplied RGBA pixels of the pixmap into a `(height, width, 4)` array.
+fn pixmap_to_rgba(
+    pixmap: &resvg::tiny_skia::Pixmap,
+) -> ndarray::Array3<u8> {
+    ndarray::Array3::from_shape_vec(
+        (pixmap.height() as usize, pixmap.width() as usize, 4),
+        pixmap.data().to_vec(),
+    )
+    .expect("a pixmap has 4 bytes per pixel")
+}
+
+/// Run `render` with a cleared pixmap of the given size.
+///
+/// Each thread keeps its pixmap around for the next frame,
+/// so rendering does not allocate a new frame sized buffer every time.
+fn with_pixmap<T>(
+    width: u32,
+    height: u32,
+    render: impl FnOnce(&mut resvg::tiny_skia::Pixmap) -> T,
+) -> Result<T, RenderError> {
+    thread_local! {
+        /// The pixmap of the last frame rendered on this thread.
+        static PIXMAP: std::cell::RefCell<Option<resvg::tiny_skia::Pixmap>> =
+            const { std::cell::RefCell::new(None) };
+    }
+
+    PIXMAP.with(|pixmap| {
+        let mut pixmap = pixmap.borrow_mut();
+        let reused = match pixmap.take() {
+            Some(mut pixmap)
+                if pixmap.width() == width
+                    && pixmap.height() == height =>
+            {
+                pixmap.fill(resvg::tiny_skia::Color::TRANSPARENT);
+                pixmap
+            }
+            _ => resvg::tiny_skia::Pixmap::new(width, height)
+                .ok_or(RenderError::Pixmap { width, height })?,
+        };
+        Ok(render(pixmap.insert(reused)))
+    })
+}
+
+/// Convert a pixmap to the `height x width x 3` RGB layout the encoder expects.
+///
+/// tiny-skia stores pixels as premultiplied RGBA,
+/// so the channels are read by name rather than relying on the byte order.
+/// The premultiplied colors are composited over `backdrop` before the alpha is dropped.
+fn pixmap_to_rgb(
+    pixmap: &resvg::tiny_skia::Pixmap,
+    backdrop: Color,
+) -> ndarray::Array3<u8> {
+    /// Composite a premultiplied channel over an opaque backdrop channel.
+    fn composite(channel: u8, alpha: u8, backdrop: u8) -> u8 {
+        let backdrop = (255 - alpha) as u16 * backdrop as u16 / 255;
+        (channel as u16 + backdrop).min(255) as u8
+    }
+
+    let data = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let alpha = pixel.alpha();
+            [
+                composite(pixel.red(), alpha, backdrop.0),
+                composite(pixel.green(), alpha, backdrop.1),
+                composite(pixel.blue(), alpha, backdrop.2),
+            ]
+        })
+        .collect::<Vec<_>>();
+    ndarray::Array3::from_shape_vec(
+        (pixmap.height() as usize, pixmap.width() as usize, 3),
+        data,
+    )
+    .expect("the pixmap has 3 channels per pixel")
+}
+
+/// Joins nodes into a single blob, without any whitespace between them.
+///
+/// The svg crate writes every child of a `<text>` element on its own line,
+/// which would add spaces between `<tspan>`s.
+pub(crate) fn join_inline<T: svg::Node>(
+    nodes: impl IntoIterator<Item = T>,
+) -> svg::node::Blob {
+    svg::node::Blob::new(
+        nodes
+            .into_iter()
+            .map(|node| node.to_string())
+            .collect::<String>(),
+    )
+}
+
+/// Format a time in seconds as a WebVTT timestamp (`hh:mm:ss.mmm`).
+fn format_vtt_time(seconds: f32) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Format points as a SVG `points` attribute.
+///
+/// If `precision` is given the coordinates are rounded to that many decimal places.
+pub(crate) fn format_points(
+    points: &[(f32, f32)],
+    precision: Option<usize>,
+) -> String {
+    points
+        .iter()
+        .map(|(x, y)| match precision {
+            Some(precision) => {
+                format!("{x:.precision$},{y:.precision$}")
+            }
+            None => format!("{},{}", x, y),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Convert a node to the paths it draws, with all transforms applied.
+///
+/// Text is converted to the outlines of its glyphs.
+pub(crate) fn flatten_paths(
+    node: Box<dyn svg::Node>,
+) -> Vec<resvg::usvg::tiny_skia_path::Path> {
+    /// Collect the paths of the group and its children.
+    fn collect(
+        group: &resvg::usvg::Group,
+        paths: &mut Vec<resvg::usvg::tiny_skia_path::Path>,
+    ) {
+        for node in group.children() {
+            match node {
+                resvg::usvg::Node::Group(group) => {
+                    collect(group, paths)
+                }
+                resvg::usvg::Node::Path(path) => paths.extend(
+                    path.data()
+                        .clone()
+                        .transform(path.abs_transform()),
+                ),
+                resvg::usvg::Node::Text(text) => {
+                    collect(text.flattened(), paths)
+                }
+                resvg::usvg::Node::Image(_) => {}
+            }
+        }
+    }
+
+    let doc = svg::Document::new().add(node);
+    let mut paths = Vec::new();
+    if let Ok(tree) =
+        convert_to_resvg(doc.to_string(), system_font_database())
+    {
+        collect(tree.root(), &mut paths);
+    }
+    paths
+}
+
+/// Format a usvg path as a SVG `d` attribute.
+pub(crate) fn path_data(
+    path: &resvg::usvg::tiny_skia_path::Path,
+) -> String {
+    segments_data(path.segments())
+}
+
+/// Format path segments as a SVG `d` attribute.
+pub(crate) fn segments_data(
+    segments: impl IntoIterator<
+        Item = resvg::usvg::tiny_skia_path::PathSegment,
+    >,
+) -> String {
+    use resvg::usvg::tiny_skia_path::PathSegment;
+
+    segments
+        .into_iter()
+        .map(|segment| match segment {
+            PathSegment::MoveTo(p) => format!("M{} {}", p.x, p.y),
+            PathSegment::LineTo(p) => format!("L{} {}", p.x, p.y),
+            PathSegment::QuadTo(p1, p) => {
+                format!("Q{} {} {} {}", p1.x, p1.y, p.x, p.y)
+            }
+            PathSegment::CubicTo(p1, p2, p) => format!(
+                "C{} {} {} {} {} {}",
+                p1.x, p1.y, p2.x, p2.y, p.x, p.y
             ),
-            &mut pixel_map.as_mut(),
+            PathSegment::Close => "Z".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Create an id that is unique within the process, for SVG elements like filters.
+///
+/// Frames are rendered in parallel from shared animations,
+/// so ids can not be derived from anything local.
+pub(crate) fn unique_id(prefix: &str) -> String {
+    /// The next id to hand out.
+    static NEXT_ID: std::sync::atomic::AtomicUsize =
+        std::sync::atomic::AtomicUsize::new(0);
+    let id =
+        NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    format!("aniy-{prefix}-{id}")
+}
+
+/// Initialize ffmpeg, only done once and only by code paths that actually encode video.
+///
+/// This way image-only workflows never have to load ffmpeg.
+fn init_video() -> Result<(), RenderError> {
+    /// The result of initializing ffmpeg.
+    static INIT: std::sync::OnceLock<Result<(), String>> =
+        std::sync::OnceLock::new();
+    INIT.get_or_init(|| {
+        video_rs::init().map_err(|error| error.to_string())
+    })
+    .clone()
+    .map_err(RenderError::Init)
+}
+
+/// Encodes frames into the output video, in one of the output formats.
+enum VideoEncoder {
+    /// H.264 through video-rs, takes RGB frames.
+    H264(video_rs::encode::Encoder),
+    /// VP9 through ffmpeg directly, takes premultiplied RGBA frames.
+    Vp9(Vp9Encoder),
+}
+
+impl VideoEncoder {
+    /// Encode the next frame, shown at `position`.
+    fn encode(
+        &mut self,
+        frame: &ndarray::Array3<u8>,
+        position: &Time,
+    ) -> Result<(), video_rs::Error> {
+        match self {
+            Self::H264(encoder) => encoder.encode(frame, position),
+            Self::Vp9(encoder) => Ok(encoder.encode(frame)?),
+        }
+    }
+
+    /// Flush the remaining frames and finish the file.
+    fn finish(&mut self) -> Result<(), video_rs::Error> {
+        match self {
+            Self::H264(encoder) => encoder.finish(),
+            Self::Vp9(encoder) => Ok(encoder.finish()?),
+        }
+    }
+}
+
+/// Encodes VP9 with an alpha channel into a WebM file.
+///
+/// video-rs can only create H.264 encoders, so this drives ffmpeg itself.
+struct Vp9Encoder {
+    /// The file being written.
+    output: video_rs::ffmpeg::format::context::Output,
+    /// The opened libvpx encoder.
+    encoder: video_rs::ffmpeg::codec::encoder::video::Encoder,
+    /// The amount of frames sent to the encoder, used as the timestamp of the next one.
+    frames: i64,
+}
+
+impl Vp9Encoder {
+    /// Open `path` for writing and set up the encoder with the given codec options.
+    fn new(
+        path: &std::path::Path,
+        width: usize,
+        height: usize,
+        fps: u32,
+        options: &std::collections::HashMap<String, String>,
+    ) -> Result<Self, video_rs::ffmpeg::Error> {
+        use video_rs::ffmpeg::{self, codec, encoder, format};
+
+        let mut output = format::output(path)?;
+        let global_header = output
+            .format()
+            .flags()
+            .contains(format::Flags::GLOBAL_HEADER);
+        let codec = encoder::find_by_name("libvpx-vp9")
+            .ok_or(ffmpeg::Error::EncoderNotFound)?;
+        let mut stream = output.add_stream(codec)?;
+
+        let mut context =
+            codec::context::Context::new_with_codec(codec)
+                .encoder()
+                .video()?;
+        context.set_width(width as u32);
+        context.set_height(height as u32);
+        context.set_format(format::Pixel::YUVA420P);
+        context.set_time_base((1, fps as i32));
+        context.set_frame_rate(Some((fps as i32, 1)));
+        if global_header {
+            context.set_flags(codec::Flags::GLOBAL_HEADER);
+        }
+
+        let mut dictionary = ffmpeg::Dictionary::new();
+        for (key, value) in options {
+            dictionary.set(key, value);
+        }
+        let encoder = context.open_with(dictionary)?;
+        stream.set_parameters(&encoder);
+        output.write_header()?;
+
+        Ok(Self {
+            output,
+            encoder,
+            frames: 0,
+        })
+    }
+
+    /// Encode a premultiplied RGBA frame.
+    fn encode(
+        &mut self,
+        rgba: &ndarray::Array3<u8>,
+    ) -> Result<(), video_rs::ffmpeg::Error> {
+        let (height, width, _) = rgba.dim();
+        let mut frame = video_rs::ffmpeg::frame::Video::new(
+            video_rs::ffmpeg::format::Pixel::YUVA420P,
+            width as u32,
+            height as u32,
         );
-        let data = pixel_map.take();
-        let mut data = ndarray::Array3::from_shape_vec(
-            (self.height, self.width, 4),
-            data,
-        )
-        .unwrap();
-        data.remove_index(ndarray::Axis(2), 3);
-        data.as_standard_layout().to_owned()
+        fill_yuva(&mut frame, rgba);
+        frame.set_pts(Some(self.frames));
+        self.frames += 1;
+
+        self.encoder.send_frame(&frame)?;
+        self.write_packets()
+    }
+
+    /// Flush the frames still in the encoder and finish the file.
+    fn finish(&mut self) -> Result<(), video_rs::ffmpeg::Error> {
+        self.encoder.send_eof()?;
+        self.write_packets()?;
+        self.output.write_trailer()
+    }
+
+    /// Write every packet the encoder has ready to the file.
+    fn write_packets(
+        &mut self,
+    ) -> Result<(), video_rs::ffmpeg::Error> {
+        use video_rs::ffmpeg::{self, error::EAGAIN};
+
+        let time_base = self.encoder.time_base();
+        let stream_time_base = self
+            .output
+            .stream(0)
+            .ok_or(ffmpeg::Error::StreamNotFound)?
+            .time_base();
+        let mut packet = ffmpeg::Packet::empty();
+        loop {
+            match self.encoder.receive_packet(&mut packet) {
+                Ok(()) => {
+                    packet.set_stream(0);
+                    packet.rescale_ts(time_base, stream_time_base);
+                    packet.write_interleaved(&mut self.output)?;
+                }
+                Err(ffmpeg::Error::Eof) => return Ok(()),
+                Err(ffmpeg::Error::Other { errno })
+                    if errno == EAGAIN =>
+                {
+                    return Ok(())
+                }
+                Err(error) => return Err(error),
+            }
+        }
     }
 }
 
-/// Convert a svg string to a resvg tree.
-fn convert_to_resvg(doc: String) -> resvg::usvg::Tree {
-    let mut fonts = resvg::usvg::fontdb::Database::new();
-    fonts.load_system_fonts();
-    resvg::usvg::Tree::from_str(&doc, &Default::default(), &fonts)
-        .unwrap()
+/// Convert a premultiplied RGBA frame to the planes of a YUVA 4:2:0 frame.
+///
+/// Uses the BT.601 limited range, which is what players assume when the file doesnt say otherwise.
+/// Chroma is averaged over each 2 by 2 block of pixels.
+fn fill_yuva(
+    frame: &mut impl PlanarFrame,
+    rgba: &ndarray::Array3<u8>,
+) {
+    let (height, width, _) = rgba.dim();
+    // The straight (not premultiplied) color of a pixel.
+    let color = |x: usize, y: usize| {
+        let alpha = rgba[[y, x, 3]] as f32;
+        let channel = |index| {
+            if alpha == 0.0 {
+                0.0
+            } else {
+                (rgba[[y, x, index]] as f32 * 255.0 / alpha)
+                    .min(255.0)
+            }
+        };
+        (channel(0), channel(1), channel(2))
+    };
+
+    let stride = frame.stride(0);
+    let luma = frame.data_mut(0);
+    for y in 0..height {
+        for x in 0..width {
+            let (r, g, b) = color(x, y);
+            luma[y * stride + x] =
+                (16.0 + 0.257 * r + 0.504 * g + 0.098 * b).round()
+                    as u8;
+        }
+    }
+
+    let stride = frame.stride(3);
+    let alpha = frame.data_mut(3);
+    for y in 0..height {
+        for x in 0..width {
+            alpha[y * stride + x] = rgba[[y, x, 3]];
+        }
+    }
+
+    let (u_stride, v_stride) = (frame.stride(1), frame.stride(2));
+    for y in 0..height.div_ceil(2) {
+        for x in 0..width.div_ceil(2) {
+            let block = [(0, 0), (1, 0), (0, 1), (1, 1)]
+                .into_iter()
+                .map(|(dx, dy)| (x * 2 + dx, y * 2 + dy))
+                .filter(|(x, y)| *x < width && *y < height)
+                .map(|(x, y)| color(x, y))
+                .collect::<Vec<_>>();
+            let count = block.len() as f32;
+            let (r, g, b) = block.iter().fold(
+                (0.0, 0.0, 0.0),
+                |(r, g, b), pixel| {
+                    (r + pixel.0, g + pixel.1, b + pixel.2)
+                },
+            );
+            let (r, g, b) = (r / count, g / count, b / count);
+
+            frame.data_mut(1)[y * u_stride + x] =
+                (128.0 - 0.148 * r - 0.291 * g + 0.439 * b).round()
+                    as u8;
+            frame.data_mut(2)[y * v_stride + x] =
+                (128.0 + 0.439 * r - 0.368 * g - 0.071 * b).round()
+                    as u8;
+        }
+    }
+}
+
+/// The planes of a video frame, so `fill_yuva` can be tested without ffmpeg.
+trait PlanarFrame {
+    /// The length in bytes of a row of the plane, padding included.
+    fn stride(&self, plane: usize) -> usize;
+    /// The bytes of the plane.
+    fn data_mut(&mut self, plane: usize) -> &mut [u8];
+}
+
+impl PlanarFrame for video_rs::ffmpeg::frame::Video {
+    fn stride(&self, plane: usize) -> usize {
+        video_rs::ffmpeg::frame::Video::stride(self, plane)
+    }
+
+    fn data_mut(&mut self, plane: usize) -> &mut [u8] {
+        video_rs::ffmpeg::frame::Video::data_mut(self, plane)
+    }
+}
+
+/// A file that is removed when this is dropped, whether rendering succeeded or not.
+struct TemporaryFile(std::path::PathBuf);
+
+impl Drop for TemporaryFile {
+    fn drop(&mut self) {
+        match std::fs::remove_file(&self.0) {
+            Err(error)
+                if error.kind() != std::io::ErrorKind::NotFound =>
+            {
+                log::warn!(
+                    "Failed to remove {}: {error}",
+                    self.0.display()
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Copy the video stream of `video` and the audio stream of `audio` into `output`.
+///
+/// Audio packets starting at or after `duration` seconds are dropped.
+fn mux_audio(
+    video: &std::path::Path,
+    audio: &std::path::Path,
+    output: &std::path::Path,
+    duration: f64,
+) -> Result<(), video_rs::ffmpeg::Error> {
+    use video_rs::ffmpeg::{self, codec, encoder, format, media};
+
+    let mut video_input = format::input(video)?;
+    let mut audio_input = format::input(audio)?;
+    let mut output_context = format::output(output)?;
+
+    let mut sources = Vec::new();
+    for (input, kind) in [
+        (&video_input, media::Type::Video),
+        (&audio_input, media::Type::Audio),
+    ] {
+        let stream = input
+            .streams()
+            .best(kind)
+            .ok_or(ffmpeg::Error::StreamNotFound)?;
+        let mut output_stream = output_context
+            .add_stream(encoder::find(codec::Id::None))?;
+        output_stream.set_parameters(stream.parameters());
+        // The tag is specific to the input container, and might not be valid in the output one.
+        // SAFETY: `parameters` points at the `codecpar` of the stream we just added,
+        // which `output_context` owns and keeps alive for this whole function.
+        // `output_stream` borrows the context mutably, so nothing else reads it meanwhile,
+        // and `codec_tag` is a plain integer that ffmpeg allows to be zeroed.
+        unsafe {
+            (*output_stream.parameters().as_mut_ptr()).codec_tag = 0;
+        }
+        sources.push((stream.index(), stream.time_base()));
+    }
+    let [(video_index, video_time_base), (audio_index, audio_time_base)] =
+        sources[..]
+    else {
+        unreachable!("one video and one audio stream were added");
+    };
+    output_context.write_header()?;
+
+    /// The packets of a single stream of the input.
+    fn stream_packets(
+        input: &mut format::context::Input,
+        index: usize,
+    ) -> std::iter::Peekable<impl Iterator<Item = ffmpeg::Packet> + '_>
+    {
+        input
+            .packets()
+            .filter_map(move |(stream, packet)| {
+                (stream.index() == index).then_some(packet)
+            })
+            .peekable()
+    }
+    /// The time of the packet in seconds.
+    fn seconds(
+        packet: &ffmpeg::Packet,
+        time_base: ffmpeg::Rational,
+    ) -> f64 {
+        packet.dts().or(packet.pts()).unwrap_or(0) as f64
+            * f64::from(time_base)
+    }
+
+    let mut video_packets =
+        stream_packets(&mut video_input, video_index);
+    let mut audio_packets =
+        stream_packets(&mut audio_input, audio_index);
+    loop {
+        let next_video = video_packets
+            .peek()
+            .map(|packet| seconds(packet, video_time_base));
+        let next_audio = audio_packets
+            .peek()
+            .map(|packet| seconds(packet, audio_time_base))
+            .filter(|time| *time < duration);
+
+        // Write the packets in time order, so the streams are interleaved.
+        let (packet, time_base, output_index) =
+            match (next_video, next_audio) {
+                (None, None) => break,
+                (Some(video), Some(audio)) if audio < video => {
+                    (audio_packets.next(), audio_time_base, 1)
+                }
+                (Some(_), _) => {
+                    (video_packets.next(), video_time_base, 0)
+                }
+                (None, Some(_)) => {
+                    (audio_packets.next(), audio_time_base, 1)
+                }
+            };
+        let Some(mut packet) = packet else {
+            break;
+        };
+
+        let output_time_base = output_context
+            .stream(output_index)
+            .ok_or(ffmpeg::Error::StreamNotFound)?
+            .time_base();
+        packet.rescale_ts(time_base, output_time_base);
+        packet.set_position(-1);
+        packet.set_stream(output_index);
+        packet.write_interleaved(&mut output_context)?;
+    }
+
+    output_context.write_trailer()
+}
+
+/// The font usvg resolved for a span of text.
+#[derive(Debug, Clone)]
+pub struct ResolvedFont {
+    /// The text the font is used for.
+    pub text: String,
+    /// The font families requested by the text, in order of preference.
+    pub requested: Vec<String>,
+    /// The family name of the resolved font, `None` if no font matched.
+    pub family: Option<String>,
+    /// The file the resolved font was loaded from, `None` if it wasnt loaded from a file.
+    pub path: Option<std::path::PathBuf>,
+}
+
+/// Collect the resolved font of each text span in the group and its children.
+///
+/// This repeats the font query usvg does when laying out the text.
+fn collect_fonts(
+    group: &resvg::usvg::Group,
+    fonts: &resvg::usvg::fontdb::Database,
+    resolved: &mut Vec<ResolvedFont>,
+) {
+    use resvg::usvg::{
+        fontdb, FontFamily, FontStretch, FontStyle, Node,
+    };
+
+    for node in group.children() {
+        let text = match node {
+            Node::Group(group) => {
+                collect_fonts(group, fonts, resolved);
+                continue;
+            }
+            Node::Text(text) => text,
+            _ => continue,
+        };
+
+        for chunk in text.chunks() {
+            for span in chunk.spans() {
+                let font = span.font();
+                let mut families = font
+                    .families()
+                    .iter()
+                    .map(|family| match family {
+                        FontFamily::Serif => fontdb::Family::Serif,
+                        FontFamily::SansSerif => {
+                            fontdb::Family::SansSerif
+                        }
+                        FontFamily::Cursive => {
+                            fontdb::Family::Cursive
+                        }
+                        FontFamily::Fantasy => {
+                            fontdb::Family::Fantasy
+                        }
+                        FontFamily::Monospace => {
+                            fontdb::Family::Monospace
+                        }
+                        FontFamily::Named(name) => {
+                            fontdb::Family::Name(name)
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                // usvg falls back to serif as well.
+                families.push(fontdb::Family::Serif);
+
+                let stretch = match font.stretch() {
+                    FontStretch::UltraCondensed => {
+                        fontdb::Stretch::UltraCondensed
+                    }
+                    FontStretch::ExtraCondensed => {
+                        fontdb::Stretch::ExtraCondensed
+                    }
+                    FontStretch::Condensed => {
+                        fontdb::Stretch::Condensed
+                    }
+                    FontStretch::SemiCondensed => {
+                        fontdb::Stretch::SemiCondensed
+                    }
+                    FontStretch::Normal => fontdb::Stretch::Normal,
+                    FontStretch::SemiExpanded => {
+                        fontdb::Stretch::SemiExpanded
+                    }
+                    FontStretch::Expanded => {
+                        fontdb::Stretch::Expanded
+                    }
+                    FontStretch::ExtraExpanded => {
+                        fontdb::Stretch::ExtraExpanded
+                    }
+                    FontStretch::UltraExpanded => {
+                        fontdb::Stretch::UltraExpanded
+                    }
+                };
+                let style = match font.style() {
+                    FontStyle::Normal => fontdb::Style::Normal,
+                    FontStyle::Italic => fontdb::Style::Italic,
+                    FontStyle::Oblique => fontdb::Style::Oblique,
+                };
+
+                let face = fonts
+                    .query(&fontdb::Query {
+                        families: &families,
+                        weight: fontdb::Weight(font.weight()),
+                        stretch,
+                        style,
+                    })
+                    .and_then(|id| fonts.face(id));
+
+                resolved.push(ResolvedFont {
+                    text: chunk.text()[span.start()..span.end()]
+                        .to_string(),
+                    requested: font
+                        .families()
+                        .iter()
+                        .map(|family| match family {
+                            FontFamily::Named(name) => name.clone(),
+                            generic => generic.to_string(),
+                        })
+                        .collect(),
+                    family: face.and_then(|face| {
+                        face.families
+                            .first()
+                            .map(|(name, _)| name.clone())
+                    }),
+                    path: face.and_then(|face| match &face.source {
+                        fontdb::Source::File(path)
+                        | fontdb::Source::SharedFile(path, _) => {
+                            Some(path.clone())
+                        }
+                        fontdb::Source::Binary(_) => None,
+                    }),
+                });
+            }
+        }
+    }
+}
+
+/// The system fonts, with the faces sorted by family, file and index.
+///
+/// Loaded once and shared by every frame.
+fn deterministic_font_database(
+) -> &'static resvg::usvg::fontdb::Database {
+    /// The sorted font database.
+    static FONTS: std::sync::OnceLock<resvg::usvg::fontdb::Database> =
+        std::sync::OnceLock::new();
+
+    FONTS.get_or_init(|| {
+        use resvg::usvg::fontdb;
+
+        let system = system_font_database();
+        let source_path = |face: &fontdb::FaceInfo| match &face.source
+        {
+            fontdb::Source::File(path)
+            | fontdb::Source::SharedFile(path, _) => {
+                Some(path.clone())
+            }
+            fontdb::Source::Binary(_) => None,
+        };
+        let mut faces = system.faces().cloned().collect::<Vec<_>>();
+        faces.sort_by_cached_key(|face| {
+            (
+                face.families.first().map(|(name, _)| name.clone()),
+                source_path(face),
+                face.index,
+            )
+        });
+
+        let mut fonts = fontdb::Database::new();
+        for face in faces {
+            fonts.push_face_info(face);
+        }
+        fonts
+    })
+}
+
+/// The fonts installed on the system.
+///
+/// Loaded once and shared by every frame.
+fn system_font_database() -> &'static resvg::usvg::fontdb::Database {
+    /// The system font database.
+    static FONTS: std::sync::OnceLock<resvg::usvg::fontdb::Database> =
+        std::sync::OnceLock::new();
+
+    FONTS.get_or_init(|| {
+        let mut fonts = resvg::usvg::fontdb::Database::new();
+        fonts.load_system_fonts();
+        fonts
+    })
+}
+
+/// Convert a svg string to a resvg tree using the given fonts.
+///
+/// Used to measure objects, which treat a document that fails to parse as empty,
+/// the error itself is reported once the frame is rendered.
+fn convert_to_resvg(
+    doc: String,
+    fonts: &resvg::usvg::fontdb::Database,
+) -> Result<resvg::usvg::Tree, RenderError> {
+    resvg::usvg::Tree::from_str(&doc, &Default::default(), fonts)
+        .map_err(RenderError::Svg)
+}
+
+/// The errors that can happen while rendering a video.
+#[derive(Debug)]
+pub enum RenderError {
+    /// Initializing ffmpeg failed.
+    Init(String),
+    /// Creating the video encoder failed.
+    CreateEncoder(video_rs::Error),
+    /// Encoding a frame failed.
+    Encode {
+        /// The index of the frame in the video.
+        frame: usize,
+        /// The error from the encoder.
+        source: video_rs::Error,
+    },
+    /// Finishing the video file failed.
+    Finish(video_rs::Error),
+    /// The SVG of a frame could not be parsed.
+    Svg(resvg::usvg::Error),
+    /// The pixmap to render a frame into could not be created.
+    ///
+    /// Happens when the size is zero or too big.
+    Pixmap {
+        /// The width of the pixmap.
+        width: u32,
+        /// The height of the pixmap.
+        height: u32,
+    },
+    /// Creating the output directory or writing a file failed.
+    Io {
+        /// The file or directory.
+        path: std::path::PathBuf,
+        /// The error from the file system.
+        source: std::io::Error,
+    },
+    /// Encoding a frame as a PNG failed.
+    Png(std::io::Error),
+    /// Adding the audio track to the video failed.
+    Mux(video_rs::ffmpeg::Error),
+    /// Encoding the GIF failed.
+    Gif(gif::EncodingError),
+    /// The encoder thread panicked, with the panic message.
+    EncoderPanicked(String),
+    /// A storyboard was requested without any rows or columns.
+    EmptyStoryboard {
+        /// The requested amount of rows.
+        rows: usize,
+        /// The requested amount of columns.
+        cols: usize,
+    },
+}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            Self::Init(error) => {
+                write!(f, "failed to initialize ffmpeg: {error}")
+            }
+            Self::CreateEncoder(error) => {
+                write!(f, "failed to create the encoder: {error}")
+            }
+            Self::Encode { frame, source } => {
+                write!(f, "failed to encode frame {frame}: {source}")
+            }
+            Self::Finish(error) => {
+                write!(f, "failed to finish the video: {error}")
+            }
+            Self::Svg(error) => {
+                write!(f, "failed to parse frame svg: {error}")
+            }
+            Self::Pixmap { width, height } => write!(
+                f,
+                "failed to create a {width}x{height} pixmap"
+            ),
+            Self::Io { path, source } => {
+                write!(
+                    f,
+                    "failed to write {}: {source}",
+                    path.display()
+                )
+            }
+            Self::Png(error) => {
+                write!(f, "failed to encode frame as png: {error}")
+            }
+            Self::Mux(error) => {
+                write!(f, "failed to add the audio track: {error}")
+            }
+            Self::Gif(error) => {
+                write!(f, "failed to encode the gif: {error}")
+            }
+            Self::EncoderPanicked(message) => {
+                write!(f, "the encoder panicked: {message}")
+            }
+            Self::EmptyStoryboard { rows, cols } => write!(
+                f,
+                "a storyboard needs at least one row and column, got {rows}x{cols}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Init(_)
+            | Self::Pixmap { .. }
+            | Self::EncoderPanicked(_)
+            | Self::EmptyStoryboard { .. } => None,
+            Self::CreateEncoder(error) | Self::Finish(error) => {
+                Some(error)
+            }
+            Self::Encode { source, .. } => Some(source),
+            Self::Io { source, .. } | Self::Png(source) => {
+                Some(source)
+            }
+            Self::Svg(error) => Some(error),
+            Self::Mux(error) => Some(error),
+            Self::Gif(error) => Some(error),
+        }
+    }
 }
 
 /// The result of rendering a video.
@@ -375,3 +3114,243 @@ impl RenderingResult {
         let _ = open::that(&self.output_location);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animations::Animation;
+
+    /// A small renderer with a fade that lasts `frames` frames.
+    fn fading_renderer(frames: u32) -> Renderer {
+        let mut renderer = Renderer::new(32, 24);
+        renderer.set_fps(frames).on_progress(|_, _| {});
+
+        let square = Arc::new(objects::Rectangle::new(20.0, 20.0));
+        renderer.timeline().add_animation(
+            animations::AnimatedObject {
+                object: square.clone(),
+                enter: animations::FadeAnimation::new(
+                    square.as_ref(),
+                )
+                .container()
+                .duration(1.0),
+                exit: animations::FadeOut::new(square.as_ref())
+                    .container()
+                    .as_exit(0.0),
+            },
+        );
+        renderer
+    }
+
+    #[test]
+    fn batched_frames_match_frame_hashes() {
+        let renderer = fading_renderer(12);
+        let frames =
+            renderer.timeline.calc_frames(renderer.fps as usize);
+        let progress = Progress::new(&renderer, frames.len());
+
+        let mut batches = 0;
+        let mut hashes = Vec::new();
+        renderer
+            .render_batches(
+                frames,
+                3,
+                &renderer.font_database(),
+                false,
+                &progress,
+                |batch| {
+                    batches += 1;
+                    hashes.extend(
+                        batch.iter().map(|frame| {
+                            fnv1a(frame.iter().copied())
+                        }),
+                    );
+                    true
+                },
+            )
+            .unwrap();
+
+        assert!(batches > 2);
+        assert_eq!(hashes, renderer.frame_hashes().unwrap());
+    }
+
+    #[test]
+    fn rendering_waits_for_the_encoder() {
+        let renderer = fading_renderer(12);
+        let frames =
+            renderer.timeline.calc_frames(renderer.fps as usize);
+        let frame_count = frames.len();
+        let progress = Progress::new(&renderer, frame_count);
+        let fonts = renderer.font_database();
+
+        let (sender, receiver) =
+            std::sync::mpsc::sync_channel(QUEUED_BATCHES);
+        let sent = std::sync::atomic::AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                renderer.render_batches(
+                    frames,
+                    1,
+                    &fonts,
+                    false,
+                    &progress,
+                    |batch| {
+                        sent.fetch_add(
+                            1,
+                            std::sync::atomic::Ordering::SeqCst,
+                        );
+                        sender.send(batch).is_ok()
+                    },
+                )
+            });
+
+            // With nothing receiving, rendering stops once the queue is full,
+            // plus the one batch waiting to be sent.
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            assert!(
+                sent.load(std::sync::atomic::Ordering::SeqCst)
+                    <= QUEUED_BATCHES + 1
+            );
+
+            let received = receiver.iter().take(frame_count).count();
+            assert_eq!(received, frame_count);
+        });
+    }
+
+    #[test]
+    fn empty_storyboard_is_an_error() {
+        let renderer = fading_renderer(12);
+        let path =
+            std::env::temp_dir().join("aniy_empty_storyboard.png");
+        for (rows, cols) in [(0, 3), (3, 0), (0, 0)] {
+            assert!(matches!(
+                renderer.storyboard(rows, cols, &path),
+                Err(RenderError::EmptyStoryboard { .. })
+            ));
+        }
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn timeline_objects_stay_in_the_first_layer() {
+        let mut timeline = Timeline::default();
+        timeline.layer("background");
+        timeline.layer("foreground").add_object(Arc::new(
+            objects::Rectangle::new(1.0, 1.0).z_index(isize::MIN),
+        ));
+        let square = Arc::new(
+            objects::Rectangle::new(1.0, 1.0).z_index(isize::MAX),
+        );
+        timeline.add_object(square.clone()).add_animation(
+            animations::AnimatedObject {
+                object: square.clone(),
+                enter: animations::FadeAnimation::new(
+                    square.as_ref(),
+                )
+                .container()
+                .duration(1.0),
+                exit: animations::FadeOut::new(square.as_ref())
+                    .container()
+                    .as_exit(1.0),
+            }
+            .lifetime(1.0),
+        );
+
+        let foreground = timeline.objects[0].0;
+        assert!(foreground >= LAYER_Z_RANGE / 2);
+        assert!(timeline.objects[1].0 < foreground);
+        let frame = timeline.frame_at(0.5, 10);
+        assert_eq!(frame.animations.len(), 1);
+        assert!(frame.animations[0].animate(0.5).0 < foreground);
+        let frame = timeline.frame_at(1.5, 10);
+        assert!(frame.objects[2].0 < foreground);
+    }
+
+    #[test]
+    fn reference_resolution_follows_the_origin() {
+        let mut before = Renderer::new(32, 24);
+        before.origin(Origin::TopLeft).reference_resolution(64, 48);
+        let mut after = Renderer::new(32, 24);
+        after.reference_resolution(64, 48).origin(Origin::TopLeft);
+        assert_eq!(before.view_rect(), (0.0, 0.0, 64.0, 48.0));
+        assert_eq!(after.view_rect(), (0.0, 0.0, 64.0, 48.0));
+
+        after.origin(Origin::Center);
+        assert_eq!(after.view_rect(), (-32.0, -24.0, 64.0, 48.0));
+        after.view_box(1.0, 2.0, 3.0, 4.0);
+        assert_eq!(after.view_rect(), (1.0, 2.0, 3.0, 4.0));
+    }
+
+    /// Planes in plain vectors, with padding after each row like ffmpeg adds.
+    struct Planes {
+        /// The bytes of the Y, U, V and A planes.
+        data: [Vec<u8>; 4],
+        /// The row length of each plane.
+        strides: [usize; 4],
+    }
+
+    impl PlanarFrame for Planes {
+        fn stride(&self, plane: usize) -> usize {
+            self.strides[plane]
+        }
+
+        fn data_mut(&mut self, plane: usize) -> &mut [u8] {
+            &mut self.data[plane]
+        }
+    }
+
+    #[test]
+    fn fill_yuva_converts_premultiplied_rgba() {
+        const RED: [u8; 4] = [255, 0, 0, 255];
+        const HALF_RED: [u8; 4] = [128, 0, 0, 128];
+        const CLEAR: [u8; 4] = [0, 0, 0, 0];
+        const WHITE: [u8; 4] = [255, 255, 255, 255];
+        const BLUE: [u8; 4] = [0, 0, 255, 255];
+        let pixels = [
+            [RED, HALF_RED, CLEAR],
+            [RED, RED, WHITE],
+            [BLUE, BLUE, WHITE],
+        ];
+        let rgba =
+            ndarray::Array3::from_shape_fn((3, 3, 4), |(y, x, c)| {
+                pixels[y][x][c]
+            });
+
+        // Padding is filled with a marker to check it is left alone.
+        let mut planes = Planes {
+            data: [vec![7; 12], vec![7; 6], vec![7; 6], vec![7; 12]],
+            strides: [4, 3, 3, 4],
+        };
+        fill_yuva(&mut planes, &rgba);
+
+        let [y, u, v, a] = planes.data;
+        assert_eq!(
+            y,
+            [82, 82, 16, 7, 82, 82, 235, 7, 41, 41, 235, 7]
+        );
+        assert_eq!(u, [90, 128, 7, 240, 128, 7]);
+        assert_eq!(v, [240, 128, 7, 110, 128, 7]);
+        assert_eq!(
+            a,
+            [255, 128, 0, 7, 255, 255, 255, 7, 255, 255, 255, 7]
+        );
+    }
+
+    #[test]
+    #[ignore = "encodes a video with ffmpeg, run with `cargo test -- --ignored`"]
+    fn render_smoke_test() {
+        let path =
+            std::env::temp_dir().join("aniy_render_smoke_test");
+        for format in [OutputFormat::Mp4H264, OutputFormat::WebmVp9] {
+            let mut renderer = fading_renderer(12);
+            renderer.format(format).output_path(&path);
+            let result = renderer.render().unwrap();
+
+            let size = std::fs::metadata(&result.output_location)
+                .unwrap()
+                .len();
+            assert!(size > 0);
+            std::fs::remove_file(&result.output_location).unwrap();
+        }
+    }
+}
@@ -2,6 +2,8 @@
 //! As well as the `Object` trait that all objects must implement,
 //! and allows you to create custom objects.
 
+use svg::Node;
+
 use crate::Color;
 
 /// The `Object` trait is implemented by all objects that can be rendered.
@@ -21,9 +23,16 @@ pub trait Object: Send + Sync {
     fn bounding_box(&self) -> resvg::usvg::Rect {
         let (_, node) = self.render();
         let doc = svg::Document::new().add(node);
-        let node = crate::convert_to_resvg(doc.to_string());
-
-        node.root().bounding_box()
+        match crate::convert_to_resvg(
+            doc.to_string(),
+            crate::system_font_database(),
+        ) {
+            Ok(node) => node.root().bounding_box(),
+            Err(_) => {
+                resvg::usvg::Rect::from_xywh(0.0, 0.0, 0.0, 0.0)
+                    .expect("an empty rect is valid")
+            }
+        }
     }
 }
 
@@ -37,6 +46,50 @@ pub enum Direction {
     Down,
 }
 
+/// The shape drawn at the ends of open strokes.
+///
+/// see: https://developer.mozilla.org/en-US/docs/Web/SVG/Attribute/stroke-linecap
+#[allow(missing_docs)] // Same as the SVG values
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+impl LineCap {
+    /// The SVG attribute value.
+    fn as_svg(self) -> &'static str {
+        match self {
+            Self::Butt => "butt",
+            Self::Round => "round",
+            Self::Square => "square",
+        }
+    }
+}
+
+/// The shape drawn at the corners of strokes.
+///
+/// see: https://developer.mozilla.org/en-US/docs/Web/SVG/Attribute/stroke-linejoin
+#[allow(missing_docs)] // Same as the SVG values
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+impl LineJoin {
+    /// The SVG attribute value.
+    fn as_svg(self) -> &'static str {
+        match self {
+            Self::Miter => "miter",
+            Self::Round => "round",
+            Self::Bevel => "bevel",
+        }
+    }
+}
+
 /// A polygon object.
 #[derive(Clone)]
 pub struct Polygon {
@@ -45,24 +98,43 @@ pub struct Polygon {
     /// The polygon is drawn by connecting the points in the order they are given.
     /// As well as the first and last point.
     pub points: Vec<(f32, f32)>,
-    /// The fill color of the polygon.
-    pub fill_color: Color,
+    /// How the inside of the polygon is painted.
+    pub fill: Fill,
     /// The outline color of the polygon.
     pub outline_color: Color,
     /// The stroke width of the polygon.
     pub stroke_width: f32,
     /// The z-index of the polygon.
     pub z_index: isize,
+    /// The amount of decimal places to use for the points in the SVG output.
+    ///
+    /// `None` uses the full float precision.
+    pub precision: Option<usize>,
+    /// The tension of a smooth curve drawn through the points, instead of straight edges.
+    ///
+    /// `None` draws straight edges.
+    pub smoothing: Option<f32>,
+    /// The alternating dash and gap lengths of the outline, `None` for a solid outline.
+    pub dash_pattern: Option<Vec<f32>>,
+    /// The shape of the ends of the dashes, uses the SVG default if not set.
+    pub line_cap: Option<LineCap>,
+    /// The shape of the corners of the outline, uses the SVG default if not set.
+    pub line_join: Option<LineJoin>,
 }
 
 impl Default for Polygon {
     fn default() -> Self {
         Self {
             points: Vec::new(),
-            fill_color: Color::rgb(255, 255, 255),
+            fill: Fill::Solid(Color::rgb(255, 255, 255)),
             outline_color: Color::rgb(100, 100, 100),
             stroke_width: 10.0,
             z_index: 0,
+            precision: None,
+            smoothing: None,
+            dash_pattern: None,
+            line_cap: None,
+            line_join: None,
         }
     }
 }
@@ -76,12 +148,108 @@ impl Polygon {
         }
     }
 
+    /// Creates a polygon from the convex hull of the given points.
+    ///
+    /// Uses Andrew's monotone chain algorithm,
+    /// points inside the hull (or on its edges) are dropped.
+    pub fn convex_hull(points: &[(f32, f32)]) -> Self {
+        let mut points = points.to_vec();
+        points.sort_by(|a, b| {
+            a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1))
+        });
+        points.dedup();
+
+        if points.len() < 3 {
+            return Self::new(points);
+        }
+
+        /// The z component of the cross product of `oa` and `ob`.
+        fn cross(o: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+            (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+        }
+
+        let mut hull: Vec<(f32, f32)> =
+            Vec::with_capacity(points.len() * 2);
+        for pass in
+            [points.clone(), points.into_iter().rev().collect()]
+        {
+            let lower_bound = hull.len();
+            for point in pass {
+                while hull.len() >= lower_bound + 2
+                    && cross(
+                        hull[hull.len() - 2],
+                        hull[hull.len() - 1],
+                        point,
+                    ) <= 0.0
+                {
+                    hull.pop();
+                }
+                hull.push(point);
+            }
+            // The last point of each pass is the first point of the next.
+            hull.pop();
+        }
+
+        Self::new(hull)
+    }
+
+    /// Creates a regular polygon with the given amount of sides.
+    ///
+    /// The vertices are `radius` away from `center`,
+    /// with the first one pointing straight up and the rest following clockwise.
+    pub fn regular(
+        sides: usize,
+        radius: f32,
+        center: (f32, f32),
+    ) -> Self {
+        Self::new(
+            (0..sides)
+                .map(|index| {
+                    polar_point(center, radius, index, sides)
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Creates a star with the given amount of points.
+    ///
+    /// The vertices alternate between `outer_radius` and `inner_radius` away from `center`,
+    /// with the first point of the star pointing straight up and the rest following clockwise.
+    pub fn star(
+        points: usize,
+        outer_radius: f32,
+        inner_radius: f32,
+        center: (f32, f32),
+    ) -> Self {
+        Self::new(
+            (0..points * 2)
+                .map(|index| {
+                    let radius = if index % 2 == 0 {
+                        outer_radius
+                    } else {
+                        inner_radius
+                    };
+                    polar_point(center, radius, index, points * 2)
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
     /// Sets the z-index of the polygon.
     pub fn z_index(mut self, z_index: isize) -> Self {
         self.z_index = z_index;
         self
     }
 
+    /// Sets the amount of decimal places used for the points in the SVG output.
+    ///
+    /// Point heavy polygons produce huge SVG strings at full precision,
+    /// which are slow to parse.
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
     /// Adds a point to the polygon.
     pub fn add_point(mut self, x: f32, y: f32) -> Self {
         self.points.push((x, y));
@@ -100,40 +268,1327 @@ impl Polygon {
         self
     }
 
-    /// Sets the fill color of the polygon.
-    pub fn fill(mut self, color: Color) -> Self {
-        self.fill_color = color;
+    /// Sets the fill of the polygon, either a `Color` or a gradient `Fill`.
+    pub fn fill(mut self, fill: impl Into<Fill>) -> Self {
+        self.fill = fill.into();
+        self
+    }
+
+    /// Sets a solid fill color, what `fill` did before it took gradients.
+    #[deprecated(note = "use `fill`, which takes a `Color` as well")]
+    pub fn fill_color(self, color: Color) -> Self {
+        self.fill(Fill::Solid(color))
+    }
+
+    /// The fill color of the polygon, or `None` if it is filled with a gradient.
+    pub fn solid_fill(&self) -> Option<&Color> {
+        match &self.fill {
+            Fill::Solid(color) => Some(color),
+            _ => None,
+        }
+    }
+
+    /// Draw a smooth curve through the points instead of straight edges.
+    ///
+    /// Uses catmull-rom splines, a tension of 0.0 gives the standard rounded curve
+    /// and 1.0 gives straight edges.
+    pub fn smooth(mut self, tension: f32) -> Self {
+        self.smoothing = Some(tension);
+        self
+    }
+
+    /// Draw the outline dashed, with alternating dash and gap lengths.
+    ///
+    /// For example `vec![20.0, 10.0]` draws dashes of 20 with gaps of 10.
+    pub fn dashed(mut self, pattern: Vec<f32>) -> Self {
+        self.dash_pattern = Some(pattern);
         self
     }
 
+    /// Sets the shape of the ends of the dashes.
+    pub fn stroke_linecap(mut self, cap: LineCap) -> Self {
+        self.line_cap = Some(cap);
+        self
+    }
+
+    /// Sets the shape of the corners of the outline.
+    pub fn stroke_linejoin(mut self, join: LineJoin) -> Self {
+        self.line_join = Some(join);
+        self
+    }
+
+    /// The SVG path data of a closed catmull-rom curve through the points.
+    fn smooth_path_data(&self, tension: f32) -> String {
+        let points = &self.points;
+        let count = points.len();
+        let Some(first) = points.first() else {
+            return String::new();
+        };
+
+        // Each control point is a sixth of the tangent away from its point.
+        let factor = (1.0 - tension) / 6.0;
+
+        let mut data = format!(
+            "M{}",
+            crate::format_points(&[*first], self.precision)
+        );
+        for i in 0..count {
+            let p0 = points[(i + count - 1) % count];
+            let p1 = points[i];
+            let p2 = points[(i + 1) % count];
+            let p3 = points[(i + 2) % count];
+
+            let c1 = (
+                p1.0 + (p2.0 - p0.0) * factor,
+                p1.1 + (p2.1 - p0.1) * factor,
+            );
+            let c2 = (
+                p2.0 - (p3.0 - p1.0) * factor,
+                p2.1 - (p3.1 - p1.1) * factor,
+            );
+            data.push_str(&format!(
+                " C{}",
+                crate::format_points(&[c1, c2, p2], self.precision)
+            ));
+        }
+        data.push_str(" Z");
+        data
+    }
+
     /// Sets the outline color of the polygon.
     pub fn outline(mut self, color: Color) -> Self {
         self.outline_color = color;
         self
     }
+
+    /// Splits the polygon into triangles using ear clipping.
+    ///
+    /// Works for simple polygons (convex or concave) in either winding order,
+    /// self-intersecting polygons will only be partially triangulated.
+    pub fn triangulate(&self) -> Vec<[(f32, f32); 3]> {
+        /// The z component of the cross product of `oa` and `ob`.
+        fn cross(o: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+            (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+        }
+
+        let points = &self.points;
+        if points.len() < 3 {
+            return vec![];
+        }
+
+        let mut remaining = (0..points.len()).collect::<Vec<_>>();
+        if self.signed_area() < 0.0 {
+            remaining.reverse();
+        }
+
+        let mut triangles = Vec::with_capacity(points.len() - 2);
+        while remaining.len() > 3 {
+            let count = remaining.len();
+            let ear = (0..count).find(|&i| {
+                let prev = points[remaining[(i + count - 1) % count]];
+                let current = points[remaining[i]];
+                let next = points[remaining[(i + 1) % count]];
+
+                cross(prev, current, next) > 0.0
+                    && remaining.iter().all(|&other| {
+                        let point = points[other];
+                        point == prev
+                            || point == current
+                            || point == next
+                            || cross(prev, current, point) < 0.0
+                            || cross(current, next, point) < 0.0
+                            || cross(next, prev, point) < 0.0
+                    })
+            });
+
+            let Some(i) = ear else {
+                log::warn!("Polygon is not simple, could not finish triangulation.");
+                return triangles;
+            };
+            triangles.push([
+                points[remaining[(i + count - 1) % count]],
+                points[remaining[i]],
+                points[remaining[(i + 1) % count]],
+            ]);
+            remaining.remove(i);
+        }
+        triangles.push([
+            points[remaining[0]],
+            points[remaining[1]],
+            points[remaining[2]],
+        ]);
+
+        triangles
+    }
+
+    /// Moves every edge of the polygon outward by `distance`, or inward if it is negative.
+    ///
+    /// Corners are mitered, with the miter length limited to `MITER_LIMIT` times the distance
+    /// so very sharp corners dont produce long spikes.
+    /// Insetting by more than the polygon is wide will turn it inside out,
+    /// this is not detected and produces a self-intersecting polygon.
+    pub fn offset(&self, distance: f32) -> Self {
+        /// The maximum length of a corner miter, relative to the distance.
+        const MITER_LIMIT: f32 = 4.0;
+
+        let points = &self.points;
+        let count = points.len();
+        if count < 3 {
+            return self.clone();
+        }
+
+        // Flip the normals of counter-clockwise polygons so they point outward.
+        let side = self.signed_area().signum();
+        let normals = (0..count)
+            .map(|i| {
+                let (a, b) = (points[i], points[(i + 1) % count]);
+                let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+                let length =
+                    (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+                (side * dy / length, -side * dx / length)
+            })
+            .collect::<Vec<_>>();
+
+        let points = (0..count)
+            .map(|i| {
+                let before = normals[(i + count - 1) % count];
+                let after = normals[i];
+                let (x, y) = points[i];
+
+                let dot = before.0 * after.0 + before.1 * after.1;
+                let miter = (before.0 + after.0, before.1 + after.1);
+                let mut scale =
+                    distance / (1.0 + dot).max(f32::EPSILON);
+
+                let length = (miter.0 * miter.0 + miter.1 * miter.1)
+                    .sqrt()
+                    * scale.abs();
+                let limit = MITER_LIMIT * distance.abs();
+                if length > limit {
+                    scale *= limit / length;
+                }
+
+                (x + miter.0 * scale, y + miter.1 * scale)
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            points,
+            ..self.clone()
+        }
+    }
+
+    /// Displace each point along its normal by a random amount up to `amplitude`,
+    /// for a sketchy, hand-drawn look.
+    ///
+    /// The same seed always gives the same polygon, so a static seed doesnt flicker between frames.
+    /// Only the existing points are moved, so add points along the edges for a rougher outline.
+    pub fn roughen(&self, amplitude: f32, seed: u64) -> Self {
+        let points = &self.points;
+        let count = points.len();
+        if count < 2 {
+            return self.clone();
+        }
+
+        let points = (0..count)
+            .map(|i| {
+                let before = points[(i + count - 1) % count];
+                let after = points[(i + 1) % count];
+                let (x, y) = points[i];
+
+                // The normal of the line between the neighbours is the average of both edge normals.
+                let (dx, dy) =
+                    (after.0 - before.0, after.1 - before.1);
+                let length =
+                    (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+                let offset =
+                    (random_unit(seed, i) * 2.0 - 1.0) * amplitude;
+
+                (x + dy / length * offset, y - dx / length * offset)
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            points,
+            ..self.clone()
+        }
+    }
+
+    /// The signed area of the polygon times two, positive for clockwise polygons.
+    fn signed_area(&self) -> f32 {
+        let points = &self.points;
+        (0..points.len())
+            .map(|i| {
+                let (a, b) =
+                    (points[i], points[(i + 1) % points.len()]);
+                a.0 * b.1 - b.0 * a.1
+            })
+            .sum::<f32>()
+    }
+}
+
+/// A pseudo random number in `0.0..1.0` derived from a seed and an index.
+///
+/// Uses the splitmix64 mixer, so the same input always gives the same number.
+fn random_unit(seed: u64, index: usize) -> f32 {
+    let mut z = seed.wrapping_add(
+        (index as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15),
+    );
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 40) as f32 / (1u64 << 24) as f32
+}
+
+impl Object for Polygon {
+    fn render(&self) -> (isize, Box<dyn svg::Node>) {
+        let mut polygon = match self.smoothing {
+            Some(tension) => {
+                let mut path =
+                    svg::node::element::Element::new("path");
+                path.assign("d", self.smooth_path_data(tension));
+                path
+            }
+            None => {
+                let mut polygon =
+                    svg::node::element::Element::new("polygon");
+                polygon.assign(
+                    "points",
+                    crate::format_points(
+                        &self.points,
+                        self.precision,
+                    ),
+                );
+                polygon
+            }
+        };
+
+        polygon.assign("stroke-width", self.stroke_width);
+        polygon.assign("stroke", self.outline_color.as_css());
+        if let Some(pattern) = &self.dash_pattern {
+            let pattern = pattern
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" ");
+            polygon.assign("stroke-dasharray", pattern);
+        }
+        if let Some(cap) = self.line_cap {
+            polygon.assign("stroke-linecap", cap.as_svg());
+        }
+        if let Some(join) = self.line_join {
+            polygon.assign("stroke-linejoin", join.as_svg());
+        }
+
+        (self.z_index, self.fill.apply(polygon))
+    }
+}
+
+/// The `index`th of `count` points evenly spaced on a circle, starting at the top and going clockwise.
+fn polar_point(
+    center: (f32, f32),
+    radius: f32,
+    index: usize,
+    count: usize,
+) -> (f32, f32) {
+    let angle = std::f32::consts::TAU * index as f32 / count as f32;
+    (
+        center.0 + radius * angle.sin(),
+        center.1 - radius * angle.cos(),
+    )
+}
+
+/// How the inside of a shape is painted.
+///
+/// A plain `Color` converts into a solid fill, so `fill` takes either.
+#[derive(Clone)]
+pub enum Fill {
+    /// A single color.
+    Solid(Color),
+    /// A gradient along a straight line across the bounding box of the shape.
+    LinearGradient {
+        /// The `(offset, color)` stops of the gradient, with offsets between 0.0 and 1.0.
+        stops: Vec<(f32, Color)>,
+        /// The direction of the gradient in degrees, clockwise from left to right.
+        angle: f32,
+    },
+    /// A gradient going outward in a circle.
+    RadialGradient {
+        /// The `(offset, color)` stops of the gradient, with offsets between 0.0 and 1.0.
+        stops: Vec<(f32, Color)>,
+        /// The center of the circle, in the same coordinates as the shape.
+        center: (f32, f32),
+        /// The radius of the circle.
+        radius: f32,
+    },
+}
+
+impl From<Color> for Fill {
+    fn from(color: Color) -> Self {
+        Self::Solid(color)
+    }
+}
+
+impl Fill {
+    /// A linear gradient at `angle` degrees clockwise from left to right.
+    pub fn linear(
+        stops: impl Into<Vec<(f32, Color)>>,
+        angle: f32,
+    ) -> Self {
+        Self::LinearGradient {
+            stops: stops.into(),
+            angle,
+        }
+    }
+
+    /// A radial gradient going outward from `center` to `radius`.
+    pub fn radial(
+        stops: impl Into<Vec<(f32, Color)>>,
+        center: (f32, f32),
+        radius: f32,
+    ) -> Self {
+        Self::RadialGradient {
+            stops: stops.into(),
+            center,
+            radius,
+        }
+    }
+
+    /// Paint the shape with this fill.
+    ///
+    /// Gradients are put in a `<defs>` next to the shape,
+    /// every one gets a unique id so any amount of them can be in the same frame.
+    fn apply(
+        &self,
+        mut shape: impl svg::Node + 'static,
+    ) -> Box<dyn svg::Node> {
+        let (id, gradient): (String, Box<dyn svg::Node>) = match self
+        {
+            Self::Solid(color) => {
+                shape.assign("fill", color.as_css());
+                return Box::new(shape);
+            }
+            Self::LinearGradient { stops, angle } => {
+                let id = crate::unique_id("gradient");
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let gradient =
+                    svg::node::element::LinearGradient::new()
+                        .set("id", id.as_str())
+                        .set("x1", 0.5 - cos / 2.0)
+                        .set("y1", 0.5 - sin / 2.0)
+                        .set("x2", 0.5 + cos / 2.0)
+                        .set("y2", 0.5 + sin / 2.0);
+                (id, Box::new(Self::add_stops(gradient, stops)))
+            }
+            Self::RadialGradient {
+                stops,
+                center,
+                radius,
+            } => {
+                let id = crate::unique_id("gradient");
+                let gradient =
+                    svg::node::element::RadialGradient::new()
+                        .set("id", id.as_str())
+                        .set("gradientUnits", "userSpaceOnUse")
+                        .set("cx", center.0)
+                        .set("cy", center.1)
+                        .set("r", *radius);
+                (id, Box::new(Self::add_stops(gradient, stops)))
+            }
+        };
+
+        shape.assign("fill", format!("url(#{id})"));
+        Box::new(
+            svg::node::element::Group::new()
+                .add(
+                    svg::node::element::Definitions::new()
+                        .add(gradient),
+                )
+                .add(shape),
+        )
+    }
+
+    /// Add a `<stop>` to the gradient element for each of the stops.
+    fn add_stops<T: svg::Node>(
+        mut gradient: T,
+        stops: &[(f32, Color)],
+    ) -> T {
+        for (offset, color) in stops {
+            gradient.append(
+                svg::node::element::Stop::new()
+                    .set("offset", *offset)
+                    .set("stop-color", color.as_css().as_ref()),
+            );
+        }
+        gradient
+    }
+}
+
+/// A shape made of straight lines and bezier curves, drawn as a SVG `<path>`.
+///
+/// Built from the same commands as SVG path data, starting each contour with `move_to`.
+/// Contours stay open unless `close` is called, open contours are still filled as if closed.
+/// Unlike `Polygon` it isnt filled by default, as most curves are just meant to be stroked.
+#[derive(Clone)]
+pub struct Path {
+    /// The commands that make up the path, in order.
+    pub segments: Vec<resvg::usvg::tiny_skia_path::PathSegment>,
+    /// How the inside of the path is painted.
+    pub fill: Fill,
+    /// The outline color of the path.
+    pub outline_color: Color,
+    /// The stroke width of the path.
+    pub stroke_width: f32,
+    /// The z-index of the path.
+    pub z_index: isize,
+}
+
+impl Default for Path {
+    fn default() -> Self {
+        Self {
+            segments: Vec::new(),
+            fill: Fill::Solid(Color(0, 0, 0, 0)),
+            outline_color: Color::rgb(255, 255, 255),
+            stroke_width: 10.0,
+            z_index: 0,
+        }
+    }
+}
+
+impl Path {
+    /// Creates a new empty path.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a path from existing segments, such as the outline of another shape.
+    pub fn from_segments(
+        segments: impl IntoIterator<
+            Item = resvg::usvg::tiny_skia_path::PathSegment,
+        >,
+    ) -> Self {
+        Self {
+            segments: segments.into_iter().collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Starts a new contour at the given point.
+    pub fn move_to(self, x: f32, y: f32) -> Self {
+        self.segment(
+            resvg::usvg::tiny_skia_path::PathSegment::MoveTo(point(
+                x, y,
+            )),
+        )
+    }
+
+    /// Draws a straight line to the given point.
+    pub fn line_to(self, x: f32, y: f32) -> Self {
+        self.segment(
+            resvg::usvg::tiny_skia_path::PathSegment::LineTo(point(
+                x, y,
+            )),
+        )
+    }
+
+    /// Draws a quadratic bezier curve to `(x, y)`, bending towards the control point `(x1, y1)`.
+    pub fn quad_to(self, x1: f32, y1: f32, x: f32, y: f32) -> Self {
+        self.segment(
+            resvg::usvg::tiny_skia_path::PathSegment::QuadTo(
+                point(x1, y1),
+                point(x, y),
+            ),
+        )
+    }
+
+    /// Draws a cubic bezier curve to `(x, y)`,
+    /// leaving towards `(x1, y1)` and arriving from the direction of `(x2, y2)`.
+    pub fn cubic_to(
+        self,
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+        x: f32,
+        y: f32,
+    ) -> Self {
+        self.segment(
+            resvg::usvg::tiny_skia_path::PathSegment::CubicTo(
+                point(x1, y1),
+                point(x2, y2),
+                point(x, y),
+            ),
+        )
+    }
+
+    /// Closes the current contour with a straight line back to its start.
+    pub fn close(self) -> Self {
+        self.segment(resvg::usvg::tiny_skia_path::PathSegment::Close)
+    }
+
+    /// Adds a segment to the end of the path.
+    fn segment(
+        mut self,
+        segment: resvg::usvg::tiny_skia_path::PathSegment,
+    ) -> Self {
+        self.segments.push(segment);
+        self
+    }
+
+    /// Sets the fill of the path, either a `Color` or a gradient `Fill`.
+    pub fn fill(mut self, fill: impl Into<Fill>) -> Self {
+        self.fill = fill.into();
+        self
+    }
+
+    /// Sets the outline color of the path.
+    pub fn outline(mut self, color: Color) -> Self {
+        self.outline_color = color;
+        self
+    }
+
+    /// Sets the stroke width of the path.
+    pub fn stroke_width(mut self, stroke_width: f32) -> Self {
+        self.stroke_width = stroke_width;
+        self
+    }
+
+    /// Sets the z-index of the path.
+    pub fn z_index(mut self, z_index: isize) -> Self {
+        self.z_index = z_index;
+        self
+    }
+}
+
+/// A usvg point from its coordinates.
+fn point(x: f32, y: f32) -> resvg::usvg::tiny_skia_path::Point {
+    resvg::usvg::tiny_skia_path::Point::from_xy(x, y)
+}
+
+impl Object for Path {
+    fn render(&self) -> (isize, Box<dyn svg::Node>) {
+        let path = svg::node::element::Path::new()
+            .set("d", crate::segments_data(self.segments.clone()))
+            .set("stroke", self.outline_color.as_css().as_ref())
+            .set("stroke-width", self.stroke_width);
+
+        (self.z_index, self.fill.apply(path))
+    }
+}
+
+/// A circle object.
+#[derive(Clone)]
+pub struct Circle {
+    /// The x position of the center.
+    pub cx: f32,
+    /// The y position of the center.
+    pub cy: f32,
+    /// The radius of the circle.
+    pub radius: f32,
+    /// How the inside of the circle is painted.
+    pub fill: Fill,
+    /// The outline color of the circle.
+    pub outline_color: Color,
+    /// The stroke width of the circle.
+    pub stroke_width: f32,
+    /// The z-index of the circle.
+    pub z_index: isize,
+}
+
+impl Default for Circle {
+    fn default() -> Self {
+        Self {
+            cx: 0.0,
+            cy: 0.0,
+            radius: 100.0,
+            fill: Fill::Solid(Color::rgb(255, 255, 255)),
+            outline_color: Color::rgb(100, 100, 100),
+            stroke_width: 10.0,
+            z_index: 0,
+        }
+    }
+}
+
+impl Circle {
+    /// Creates a new circle centered on the origin with the given radius.
+    pub fn new(radius: f32) -> Self {
+        Self {
+            radius,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the z-index of the circle.
+    pub fn z_index(mut self, z_index: isize) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    /// Sets the center of the circle.
+    pub fn at(mut self, x: f32, y: f32) -> Self {
+        self.cx = x;
+        self.cy = y;
+        self
+    }
+
+    /// Sets the radius of the circle.
+    pub fn radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// Shifts the circle by the given amount.
+    pub fn shift(mut self, x: f32, y: f32) -> Self {
+        self.cx += x;
+        self.cy += y;
+        self
+    }
+
+    /// Sets the fill of the circle, either a `Color` or a gradient `Fill`.
+    pub fn fill(mut self, fill: impl Into<Fill>) -> Self {
+        self.fill = fill.into();
+        self
+    }
+
+    /// Sets the outline color of the circle.
+    pub fn outline(mut self, color: Color) -> Self {
+        self.outline_color = color;
+        self
+    }
+}
+
+impl Object for Circle {
+    fn render(&self) -> (isize, Box<dyn svg::Node>) {
+        let circle = svg::node::element::Circle::new()
+            .set("cx", self.cx)
+            .set("cy", self.cy)
+            .set("r", self.radius)
+            .set("stroke", self.outline_color.as_css().as_ref())
+            .set("stroke-width", self.stroke_width);
+
+        (self.z_index, self.fill.apply(circle))
+    }
+}
+
+/// A rectangle object, optionally with rounded corners.
+#[derive(Clone)]
+pub struct Rectangle {
+    /// The x position of the top left corner.
+    pub x: f32,
+    /// The y position of the top left corner.
+    pub y: f32,
+    /// The width of the rectangle.
+    pub width: f32,
+    /// The height of the rectangle.
+    pub height: f32,
+    /// The radius of the rounded corners, 0.0 for sharp corners.
+    pub rx: f32,
+    /// How the inside of the rectangle is painted.
+    pub fill: Fill,
+    /// The outline color of the rectangle.
+    pub outline_color: Color,
+    /// The stroke width of the rectangle.
+    pub stroke_width: f32,
+    /// The z-index of the rectangle.
+    pub z_index: isize,
+}
+
+impl Rectangle {
+    /// Creates a new rectangle centered on the origin.
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            x: -width / 2.0,
+            y: -height / 2.0,
+            width,
+            height,
+            rx: 0.0,
+            fill: Fill::Solid(Color::rgb(255, 255, 255)),
+            outline_color: Color::rgb(100, 100, 100),
+            stroke_width: 10.0,
+            z_index: 0,
+        }
+    }
+
+    /// Sets the z-index of the rectangle.
+    pub fn z_index(mut self, z_index: isize) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    /// Moves the top left corner of the rectangle to the given position.
+    pub fn at(mut self, x: f32, y: f32) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    /// Rounds the corners with the given radius.
+    pub fn corner_radius(mut self, radius: f32) -> Self {
+        self.rx = radius;
+        self
+    }
+
+    /// Sets the fill of the rectangle, either a `Color` or a gradient `Fill`.
+    pub fn fill(mut self, fill: impl Into<Fill>) -> Self {
+        self.fill = fill.into();
+        self
+    }
+
+    /// Sets the outline color of the rectangle.
+    pub fn outline(mut self, color: Color) -> Self {
+        self.outline_color = color;
+        self
+    }
+}
+
+impl Object for Rectangle {
+    fn render(&self) -> (isize, Box<dyn svg::Node>) {
+        let rectangle = svg::node::element::Rectangle::new()
+            .set("x", self.x)
+            .set("y", self.y)
+            .set("width", self.width)
+            .set("height", self.height)
+            .set("rx", self.rx)
+            .set("ry", self.rx)
+            .set("stroke", self.outline_color.as_css().as_ref())
+            .set("stroke-width", self.stroke_width);
+
+        (self.z_index, self.fill.apply(rectangle))
+    }
+}
+
+/// A straight line between two points.
+#[derive(Clone)]
+pub struct Line {
+    /// The start of the line.
+    pub from: (f32, f32),
+    /// The end of the line.
+    pub to: (f32, f32),
+    /// The color of the line.
+    pub color: Color,
+    /// The stroke width of the line.
+    pub stroke_width: f32,
+    /// The z-index of the line.
+    pub z_index: isize,
+}
+
+impl Line {
+    /// Creates a new line from `from` to `to`.
+    pub fn new(from: (f32, f32), to: (f32, f32)) -> Self {
+        Self {
+            from,
+            to,
+            color: Color::rgb(255, 255, 255),
+            stroke_width: 10.0,
+            z_index: 0,
+        }
+    }
+
+    /// Sets the color of the line.
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Sets the stroke width of the line.
+    pub fn stroke_width(mut self, stroke_width: f32) -> Self {
+        self.stroke_width = stroke_width;
+        self
+    }
+
+    /// Sets the z-index of the line.
+    pub fn z_index(mut self, z_index: isize) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    /// Render the line drawn from the start up to `progress` of its length.
+    pub(crate) fn render_partial(
+        &self,
+        progress: f32,
+    ) -> (isize, Box<dyn svg::Node>) {
+        let progress = progress.clamp(0.0, 1.0);
+        let end = (
+            self.from.0 + (self.to.0 - self.from.0) * progress,
+            self.from.1 + (self.to.1 - self.from.1) * progress,
+        );
+        let line = svg::node::element::Line::new()
+            .set("x1", self.from.0)
+            .set("y1", self.from.1)
+            .set("x2", end.0)
+            .set("y2", end.1)
+            .set("stroke", self.color.as_css().as_ref())
+            .set("stroke-width", self.stroke_width);
+
+        (self.z_index, Box::new(line))
+    }
+}
+
+impl Object for Line {
+    fn render(&self) -> (isize, Box<dyn svg::Node>) {
+        self.render_partial(1.0)
+    }
+}
+
+/// A straight arrow with a triangular head at the `to` end.
+#[derive(Clone)]
+pub struct Arrow {
+    /// The start of the arrow.
+    pub from: (f32, f32),
+    /// The tip of the arrow.
+    pub to: (f32, f32),
+    /// The color of the line and the head.
+    pub color: Color,
+    /// The stroke width of the line.
+    pub stroke_width: f32,
+    /// The length and width of the head.
+    pub head_size: f32,
+    /// The z-index of the arrow.
+    pub z_index: isize,
 }
 
-impl Object for Polygon {
-    fn render(&self) -> (isize, Box<dyn svg::Node>) {
-        let mut polygon = svg::node::element::Polygon::new();
-
-        polygon = polygon
-            .set(
-                "points",
-                self.points
-                    .iter()
-                    .map(|(x, y)| format!("{},{}", x, y))
-                    .collect::<Vec<_>>()
-                    .join(" "),
-            )
-            .set("stroke-width", self.stroke_width);
+impl Arrow {
+    /// Creates a new arrow pointing from `from` to `to`.
+    pub fn new(from: (f32, f32), to: (f32, f32)) -> Self {
+        Self {
+            from,
+            to,
+            color: Color::rgb(255, 255, 255),
+            stroke_width: 10.0,
+            head_size: 40.0,
+            z_index: 0,
+        }
+    }
+
+    /// Sets the color of the arrow.
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Sets the stroke width of the line.
+    pub fn stroke_width(mut self, stroke_width: f32) -> Self {
+        self.stroke_width = stroke_width;
+        self
+    }
+
+    /// Sets the length and width of the head.
+    pub fn head_size(mut self, head_size: f32) -> Self {
+        self.head_size = head_size;
+        self
+    }
+
+    /// Sets the z-index of the arrow.
+    pub fn z_index(mut self, z_index: isize) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    /// Render the arrow drawn from the start up to `progress` of its length.
+    ///
+    /// The head only shows up once the drawn part reaches the start of the head,
+    /// and sits on the tip of the drawn part.
+    pub(crate) fn render_partial(
+        &self,
+        progress: f32,
+    ) -> (isize, Box<dyn svg::Node>) {
+        let (dx, dy) =
+            (self.to.0 - self.from.0, self.to.1 - self.from.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        let angle = dy.atan2(dx);
+        let (sin, cos) = angle.sin_cos();
+        let point_at = |distance: f32| {
+            (
+                self.from.0 + cos * distance,
+                self.from.1 + sin * distance,
+            )
+        };
+
+        let drawn = length * progress.clamp(0.0, 1.0);
+        let shaft = (length - self.head_size).max(0.0);
+        let line_end = point_at(drawn.min(shaft));
+
+        let (_, line) = Line::new(self.from, line_end)
+            .color(self.color)
+            .stroke_width(self.stroke_width)
+            .render();
+        let mut group = svg::node::element::Group::new().add(line);
+
+        if drawn > 0.0 && drawn >= shaft {
+            let tip = point_at(drawn);
+            let base = point_at(drawn - self.head_size);
+            let (nx, ny) = (
+                -sin * self.head_size / 2.0,
+                cos * self.head_size / 2.0,
+            );
+            let head = [
+                tip,
+                (base.0 + nx, base.1 + ny),
+                (base.0 - nx, base.1 - ny),
+            ];
+            group = group.add(
+                svg::node::element::Polygon::new()
+                    .set("points", crate::format_points(&head, None))
+                    .set("fill", self.color.as_css().as_ref()),
+            );
+        }
+
+        (self.z_index, Box::new(group))
+    }
+}
+
+impl Object for Arrow {
+    fn render(&self) -> (isize, Box<dyn svg::Node>) {
+        self.render_partial(1.0)
+    }
+}
+
+/// A horizontal curly brace, pointing down at its middle.
+///
+/// Usually placed under a part of an equation with `Brace::under`.
+#[derive(Clone)]
+pub struct Brace {
+    /// The x position of the left end.
+    pub left: f32,
+    /// The x position of the right end.
+    pub right: f32,
+    /// The y position of the ends.
+    pub y: f32,
+    /// How far the tip in the middle sticks out below the ends.
+    pub depth: f32,
+    /// The color of the brace.
+    pub color: Color,
+    /// The stroke width of the brace.
+    pub stroke_width: f32,
+    /// The z-index of the brace.
+    pub z_index: isize,
+}
+
+/// The space between a bounding box and a brace or underline under it.
+pub(crate) const UNDER_GAP: f32 = 10.0;
+
+impl Brace {
+    /// Creates a brace spanning the width of the bounding box, just below it.
+    pub fn under(bounds: resvg::usvg::Rect) -> Self {
+        Self {
+            left: bounds.left(),
+            right: bounds.right(),
+            y: bounds.bottom() + UNDER_GAP,
+            depth: 30.0,
+            color: Color::rgb(255, 255, 255),
+            stroke_width: 5.0,
+            z_index: 0,
+        }
+    }
+
+    /// Sets how far the tip in the middle sticks out below the ends.
+    pub fn depth(mut self, depth: f32) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Sets the color of the brace.
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Sets the stroke width of the brace.
+    pub fn stroke_width(mut self, stroke_width: f32) -> Self {
+        self.stroke_width = stroke_width;
+        self
+    }
+
+    /// Sets the z-index of the brace.
+    pub fn z_index(mut self, z_index: isize) -> Self {
+        self.z_index = z_index;
+        self
+    }
+}
+
+impl Object for Brace {
+    fn render(&self) -> (isize, Box<dyn svg::Node>) {
+        let (left, right, y) = (self.left, self.right, self.y);
+        let middle = (left + right) / 2.0;
+        // The curls take up half the depth each, but never more than a quarter of the width.
+        let curl = (self.depth / 2.0).min((right - left).abs() / 4.0);
+        let half = y + self.depth / 2.0;
+
+        let data = format!(
+            "M {left} {y} Q {left} {half} {} {half} \
+             L {} {half} Q {middle} {half} {middle} {} \
+             Q {middle} {half} {} {half} \
+             L {} {half} Q {right} {half} {right} {y}",
+            left + curl,
+            middle - curl,
+            y + self.depth,
+            middle + curl,
+            right - curl,
+        );
+        let path = svg::node::element::Path::new()
+            .set("d", data)
+            .set("fill", "none")
+            .set("stroke", self.color.as_css().as_ref())
+            .set("stroke-width", self.stroke_width);
+
+        (self.z_index, Box::new(path))
+    }
+}
+
+/// A 2D coordinate system with ticks, labels and optional grid lines.
+///
+/// The axes cross at the origin of the data,
+/// or at the closest edge if the origin is outside of the range.
+/// Use `point_to_screen` to place other objects in data coordinates.
+#[derive(Clone)]
+pub struct Axes {
+    /// The `(min, max)` of the x axis in data coordinates.
+    pub x_range: (f32, f32),
+    /// The `(min, max)` of the y axis in data coordinates.
+    pub y_range: (f32, f32),
+    /// The width of the area covered by the axes.
+    pub width: f32,
+    /// The height of the area covered by the axes.
+    pub height: f32,
+    /// The center of the area covered by the axes.
+    pub center: (f32, f32),
+    /// The distance between the ticks on the x and y axis in data coordinates.
+    ///
+    /// A spacing of 0.0 or less draws no ticks on that axis.
+    pub tick_spacing: (f32, f32),
+    /// Whether the ticks are labeled with their value.
+    pub labels: bool,
+    /// Whether grid lines are drawn at every tick.
+    pub grid: bool,
+    /// The color of the axes, ticks and labels.
+    pub color: Color,
+    /// The color of the grid lines.
+    pub grid_color: Color,
+    /// The stroke width of the axes and ticks.
+    pub stroke_width: f32,
+    /// The font size of the labels.
+    pub font_size: f32,
+    /// The z-index of the axes.
+    pub z_index: isize,
+}
+
+/// The length of the ticks on the axes.
+const TICK_LENGTH: f32 = 20.0;
+
+impl Axes {
+    /// Creates new axes covering the given ranges, centered on the origin.
+    pub fn new(x_range: (f32, f32), y_range: (f32, f32)) -> Self {
+        Self {
+            x_range,
+            y_range,
+            width: 1600.0,
+            height: 900.0,
+            center: (0.0, 0.0),
+            tick_spacing: (1.0, 1.0),
+            labels: true,
+            grid: false,
+            color: Color::rgb(255, 255, 255),
+            grid_color: Color::rgb(60, 60, 60),
+            stroke_width: 4.0,
+            font_size: 30.0,
+            z_index: 0,
+        }
+    }
+
+    /// Sets the size of the area covered by the axes.
+    pub fn size(mut self, width: f32, height: f32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Sets the center of the area covered by the axes.
+    pub fn at(mut self, x: f32, y: f32) -> Self {
+        self.center = (x, y);
+        self
+    }
+
+    /// Sets the distance between the ticks on the x and y axis.
+    pub fn ticks(mut self, x: f32, y: f32) -> Self {
+        self.tick_spacing = (x, y);
+        self
+    }
+
+    /// Sets whether the ticks are labeled.
+    pub fn labels(mut self, enabled: bool) -> Self {
+        self.labels = enabled;
+        self
+    }
+
+    /// Sets whether grid lines are drawn.
+    pub fn grid(mut self, enabled: bool) -> Self {
+        self.grid = enabled;
+        self
+    }
+
+    /// Sets the color of the axes, ticks and labels.
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Sets the z-index of the axes.
+    pub fn z_index(mut self, z_index: isize) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    /// Converts a point in data coordinates to the position on screen.
+    pub fn point_to_screen(&self, x: f32, y: f32) -> (f32, f32) {
+        let (x_min, x_max) = self.x_range;
+        let (y_min, y_max) = self.y_range;
+        let left = self.center.0 - self.width / 2.0;
+        let bottom = self.center.1 + self.height / 2.0;
+
+        (
+            left + (x - x_min) / (x_max - x_min) * self.width,
+            bottom - (y - y_min) / (y_max - y_min) * self.height,
+        )
+    }
+
+    /// The data coordinates the axes cross at.
+    fn origin(&self) -> (f32, f32) {
+        (
+            0.0_f32.clamp(self.x_range.0, self.x_range.1),
+            0.0_f32.clamp(self.y_range.0, self.y_range.1),
+        )
+    }
+
+    /// The values of the ticks within the range, leaving out the crossing point.
+    fn tick_values(
+        range: (f32, f32),
+        spacing: f32,
+        origin: f32,
+    ) -> Vec<f32> {
+        if spacing <= 0.0 {
+            return Vec::new();
+        }
+
+        let first = (range.0 / spacing).ceil() as i64;
+        let last = (range.1 / spacing).floor() as i64;
+        (first..=last)
+            .map(|index| index as f32 * spacing)
+            .filter(|value| *value != origin)
+            .collect()
+    }
+
+    /// Formats a tick value without floating point noise.
+    fn label(value: f32) -> String {
+        /// The amount of decimals the labels are rounded to.
+        const PRECISION: f32 = 10_000.0;
+
+        format!("{}", (value * PRECISION).round() / PRECISION)
+    }
+}
+
+impl Object for Axes {
+    fn render(&self) -> (isize, Box<dyn svg::Node>) {
+        let (x_min, x_max) = self.x_range;
+        let (y_min, y_max) = self.y_range;
+        let (origin_x, origin_y) = self.origin();
+        let x_ticks = Self::tick_values(
+            self.x_range,
+            self.tick_spacing.0,
+            origin_x,
+        );
+        let y_ticks = Self::tick_values(
+            self.y_range,
+            self.tick_spacing.1,
+            origin_y,
+        );
+        let (axis_x, axis_y) =
+            self.point_to_screen(origin_x, origin_y);
+
+        let mut group = svg::node::element::Group::new();
+        let mut add = |object: &dyn Object| {
+            group.append(object.render().1);
+        };
+
+        if self.grid {
+            for &x in &x_ticks {
+                let line = Line::new(
+                    self.point_to_screen(x, y_min),
+                    self.point_to_screen(x, y_max),
+                );
+                add(&line
+                    .color(self.grid_color)
+                    .stroke_width(self.stroke_width / 2.0));
+            }
+            for &y in &y_ticks {
+                let line = Line::new(
+                    self.point_to_screen(x_min, y),
+                    self.point_to_screen(x_max, y),
+                );
+                add(&line
+                    .color(self.grid_color)
+                    .stroke_width(self.stroke_width / 2.0));
+            }
+        }
+
+        let x_axis = Line::new(
+            self.point_to_screen(x_min, origin_y),
+            self.point_to_screen(x_max, origin_y),
+        );
+        let y_axis = Line::new(
+            self.point_to_screen(origin_x, y_min),
+            self.point_to_screen(origin_x, y_max),
+        );
+        for axis in [x_axis, y_axis] {
+            add(&axis
+                .color(self.color)
+                .stroke_width(self.stroke_width));
+        }
+
+        for &x in &x_ticks {
+            let (screen_x, _) = self.point_to_screen(x, origin_y);
+            let tick = Line::new(
+                (screen_x, axis_y - TICK_LENGTH / 2.0),
+                (screen_x, axis_y + TICK_LENGTH / 2.0),
+            );
+            add(&tick
+                .color(self.color)
+                .stroke_width(self.stroke_width));
+
+            if self.labels {
+                let label = Text::new(Self::label(x))
+                    .size(self.font_size)
+                    .color(self.color)
+                    .at(
+                        screen_x,
+                        axis_y + TICK_LENGTH + self.font_size,
+                    );
+                add(&label);
+            }
+        }
+        for &y in &y_ticks {
+            let (_, screen_y) = self.point_to_screen(origin_x, y);
+            let tick = Line::new(
+                (axis_x - TICK_LENGTH / 2.0, screen_y),
+                (axis_x + TICK_LENGTH / 2.0, screen_y),
+            );
+            add(&tick
+                .color(self.color)
+                .stroke_width(self.stroke_width));
 
-        polygon =
-            polygon.set("fill", self.fill_color.as_css().as_ref());
-        polygon = polygon
-            .set("stroke", self.outline_color.as_css().as_ref());
+            if self.labels {
+                let label = Text::new(Self::label(y))
+                    .size(self.font_size)
+                    .color(self.color)
+                    .anchor("end")
+                    .at(
+                        axis_x - TICK_LENGTH,
+                        screen_y + self.font_size / 3.0,
+                    );
+                add(&label);
+            }
+        }
 
-        (self.z_index, Box::new(polygon))
+        (self.z_index, Box::new(group))
     }
 }
 
@@ -149,6 +1604,8 @@ pub struct Text {
     /// The font size of the text.
     pub font_size: f32,
     /// The color of the text.
+    ///
+    /// The alpha is applied through the fill color only, never as a separate `fill-opacity`.
     pub color: Color,
     /// The anchor of the text.
     /// This is where the x and y position of the text is relative to the actual text.
@@ -157,6 +1614,18 @@ pub struct Text {
     pub anchor: String,
     /// The z-index of the text.
     pub z_index: isize,
+    /// The maximum width of a line before the text is wrapped.
+    pub wrap_width: Option<f32>,
+    /// The font family of the text, uses the renderer default if not set.
+    pub font_family: Option<String>,
+    /// The weight of the font, from 100 to 900 where 400 is normal and 700 is bold.
+    pub font_weight: Option<u16>,
+    /// Whether the italic style of the font is used.
+    pub italic: bool,
+    /// The tab stops as offsets from `x`, sorted from left to right.
+    pub tab_stops: Vec<f32>,
+    /// The distance between the baselines of the lines, relative to the font size.
+    pub line_height: f32,
 }
 
 impl Text {
@@ -170,6 +1639,12 @@ impl Text {
             color: Color::rgb(255, 255, 255),
             anchor: "middle".to_string(),
             z_index: 0,
+            wrap_width: None,
+            font_family: None,
+            font_weight: None,
+            italic: false,
+            tab_stops: Vec::new(),
+            line_height: 1.2,
         }
     }
 
@@ -200,6 +1675,31 @@ impl Text {
         self
     }
 
+    /// Sets the font family of the text.
+    ///
+    /// Use `Renderer::resolved_fonts` to check which font is actually used.
+    pub fn font_family(mut self, name: impl Into<String>) -> Self {
+        self.font_family = Some(name.into());
+        self
+    }
+
+    /// Sets the weight of the font, from 100 to 900 where 400 is normal.
+    pub fn font_weight(mut self, weight: u16) -> Self {
+        self.font_weight = Some(weight);
+        self
+    }
+
+    /// Use the bold weight of the font.
+    pub fn bold(self) -> Self {
+        self.font_weight(700)
+    }
+
+    /// Use the italic style of the font.
+    pub fn italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+
     /// Sets the color of the text.
     pub fn color(mut self, color: Color) -> Self {
         self.color = color;
@@ -228,7 +1728,124 @@ impl Text {
         self
     }
 
+    /// Move the text so the center of the rendered glyphs is on the given point.
+    ///
+    /// Unlike `at`, this does not depend on the anchor or the baseline.
+    pub fn center_on(self, x: f32, y: f32) -> Self {
+        let bounding_box = self.bounding_box();
+        let center_x = bounding_box.x() + bounding_box.width() / 2.0;
+        let center_y = bounding_box.y() + bounding_box.height() / 2.0;
+        self.shift(x - center_x, y - center_y)
+    }
+
+    /// Sets the distance between the baselines of the lines, relative to the font size.
+    ///
+    /// Defaults to 1.2.
+    pub fn line_height(mut self, line_height: f32) -> Self {
+        self.line_height = line_height;
+        self
+    }
+
+    /// Wrap the text at word boundaries to keep each line within `max_width`.
+    ///
+    /// A single word wider than `max_width` still gets a line of its own.
+    pub fn wrap_width(mut self, max_width: f32) -> Self {
+        self.wrap_width = Some(max_width);
+        self
+    }
+
+    /// Align the text after each tab character to the next of the given stops.
+    ///
+    /// The stops are offsets from `x`, a tab past the last stop is just dropped.
+    /// Each segment is placed with the anchor of the text,
+    /// so the stops line up the start of the columns with a `start` anchor
+    /// and the end of them with an `end` anchor.
+    pub fn tab_stops(mut self, mut stops: Vec<f32>) -> Self {
+        stops.sort_by(|a, b| a.total_cmp(b));
+        self.tab_stops = stops;
+        self
+    }
+
+    /// Splits a line at its tabs into segments with their offset from `x`.
+    ///
+    /// Without tab stops the line is kept as is.
+    fn tab_segments(&self, line: &str) -> Vec<(f32, String)> {
+        if self.tab_stops.is_empty() {
+            return vec![(0.0, line.to_string())];
+        }
+
+        let mut segments: Vec<(f32, String)> = vec![];
+        for segment in line.split('\t') {
+            let offset = match segments.last() {
+                None => 0.0,
+                Some((offset, previous)) => {
+                    let mut text = self.clone();
+                    text.text = previous.clone();
+                    text.wrap_width = None;
+                    text.tab_stops = Vec::new();
+                    let end = offset + text.bounding_box().width();
+                    self.tab_stops
+                        .iter()
+                        .copied()
+                        .find(|stop| *stop > end)
+                        .unwrap_or(end)
+                }
+            };
+            segments.push((offset, segment.to_string()));
+        }
+
+        segments
+    }
+
+    /// Gets the lines the text will be rendered as.
+    ///
+    /// The text is split at every newline,
+    /// with a `wrap_width` the words of each of those lines are also measured
+    /// and greedily packed into lines.
+    pub fn lines(&self) -> Vec<String> {
+        let Some(max_width) = self.wrap_width else {
+            return self
+                .text
+                .split('\n')
+                .map(str::to_string)
+                .collect();
+        };
+
+        let measure = |line: &str| {
+            let mut text = self.clone();
+            text.text = line.to_string();
+            text.wrap_width = None;
+            text.bounding_box().width()
+        };
+
+        let mut lines = vec![];
+        for paragraph in self.text.split('\n') {
+            let mut current = String::new();
+            for word in paragraph.split_whitespace() {
+                if current.is_empty() {
+                    current = word.to_string();
+                    continue;
+                }
+
+                let candidate = format!("{} {}", current, word);
+                if measure(&candidate) > max_width {
+                    lines.push(std::mem::replace(
+                        &mut current,
+                        word.to_string(),
+                    ));
+                } else {
+                    current = candidate;
+                }
+            }
+            lines.push(current);
+        }
+
+        lines
+    }
+
     /// Gets the duration it would take to type out the text with a specific wpm.
+    ///
+    /// Counts characters rather than bytes, the same way `TextType` types them out.
     pub fn wpm(&self, wpm: f32) -> f32 {
         /// The average word length in characters.
         /// Because ofc the typing speed will be constant, but it is given in words per minute.
@@ -236,27 +1853,297 @@ impl Text {
         /// (wpm is really a bad way to measure typing speed, but it is what it is)
         const AVG_WORD_LENGTH: f32 = 5.0;
 
-        self.text.len() as f32 / AVG_WORD_LENGTH / wpm * 60.0
+        self.text.chars().count() as f32 / AVG_WORD_LENGTH / wpm
+            * 60.0
     }
 }
 
-impl Object for Text {
-    fn render(&self) -> (isize, Box<dyn svg::Node>) {
-        let mut text =
-            svg::node::element::Text::new(self.text.clone());
-
-        text = text
+impl Text {
+    /// Creates the `<text>` element with the given content and all the styling of the text.
+    ///
+    /// Used by animations that need to control the content of the element.
+    pub(crate) fn element(
+        &self,
+        content: impl Into<String>,
+    ) -> svg::node::element::Text {
+        let mut element = svg::node::element::Text::new(content)
             .set("x", self.x)
             .set("y", self.y)
             .set("font-size", self.font_size)
             .set("fill", self.color.as_css().as_ref())
-            .set("fill-opacity", self.color.3 as f32 / 255.0)
             .set("text-anchor", self.anchor.as_str());
 
+        if let Some(family) = &self.font_family {
+            element = element.set("font-family", family.as_str());
+        }
+        if let Some(weight) = self.font_weight {
+            element = element.set("font-weight", weight);
+        }
+        if self.italic {
+            element = element.set("font-style", "italic");
+        }
+        element
+    }
+}
+
+impl Object for Text {
+    fn render(&self) -> (isize, Box<dyn svg::Node>) {
+        let lines = self.lines();
+        if lines.len() == 1 && self.tab_stops.is_empty() {
+            return (
+                self.z_index,
+                Box::new(self.element(self.text.clone())),
+            );
+        }
+
+        let spans = lines.into_iter().enumerate().flat_map(
+            |(index, line)| {
+                self.tab_segments(&line).into_iter().map(
+                    move |(offset, segment)| {
+                        // Every line is its own text chunk, so the anchor applies per line.
+                        svg::node::element::TSpan::new(segment)
+                            .set("x", self.x + offset)
+                            .set(
+                                "y",
+                                self.y
+                                    + index as f32
+                                        * self.font_size
+                                        * self.line_height,
+                            )
+                    },
+                )
+            },
+        );
+        let text = self.element("").add(crate::join_inline(spans));
+
         (self.z_index, Box::new(text))
     }
 }
 
+/// A group of objects that are transformed together.
+///
+/// The children are drawn in order of their z-index inside a single `<g>` element,
+/// the z-index of the children only orders them within the group.
+/// The transformations are applied around the origin in the order they are called.
+#[derive(Clone)]
+pub struct Group {
+    /// The objects in the group.
+    pub children: Vec<std::sync::Arc<dyn Object>>,
+    /// The transform applied to every child.
+    pub transform: resvg::usvg::Transform,
+    /// The z-index of the group.
+    pub z_index: isize,
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Self {
+            children: Vec::new(),
+            transform: resvg::usvg::Transform::identity(),
+            z_index: 0,
+        }
+    }
+}
+
+impl Group {
+    /// Creates a new empty group.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an object to the group.
+    pub fn child(self, object: impl Object + 'static) -> Self {
+        self.child_arc(std::sync::Arc::new(object))
+    }
+
+    /// Adds an object that is shared with other groups or animations to the group.
+    pub fn child_arc(
+        mut self,
+        object: std::sync::Arc<dyn Object>,
+    ) -> Self {
+        self.children.push(object);
+        self
+    }
+
+    /// Moves the whole group by the given amount.
+    pub fn translate(mut self, x: f32, y: f32) -> Self {
+        self.transform = self.transform.post_translate(x, y);
+        self
+    }
+
+    /// Scales the whole group by the given factor.
+    pub fn scale(mut self, factor: f32) -> Self {
+        self.transform = self.transform.post_scale(factor, factor);
+        self
+    }
+
+    /// Rotates the whole group clockwise by the given amount of degrees.
+    pub fn rotate(mut self, degrees: f32) -> Self {
+        self.transform = self.transform.post_concat(
+            resvg::usvg::Transform::from_rotate(degrees),
+        );
+        self
+    }
+
+    /// Sets the z-index of the group.
+    pub fn z_index(mut self, z_index: isize) -> Self {
+        self.z_index = z_index;
+        self
+    }
+}
+
+impl Object for Group {
+    fn render(&self) -> (isize, Box<dyn svg::Node>) {
+        let mut children = self
+            .children
+            .iter()
+            .map(|child| child.render())
+            .collect::<Vec<_>>();
+        children.sort_by_key(|(z, _)| *z);
+
+        let transform = self.transform;
+        let mut group = svg::node::element::Group::new().set(
+            "transform",
+            format!(
+                "matrix({} {} {} {} {} {})",
+                transform.sx,
+                transform.ky,
+                transform.kx,
+                transform.sy,
+                transform.tx,
+                transform.ty
+            ),
+        );
+        for (_, child) in children {
+            group.append(child);
+        }
+
+        (self.z_index, Box::new(group))
+    }
+}
+
+/// An embedded raster image, such as a PNG or JPEG file.
+///
+/// The image data is stored as a base64 data URI,
+/// so the file is only read once when the image is created.
+#[derive(Clone)]
+pub struct Image {
+    /// The `data:` URI holding the encoded image.
+    pub href: String,
+    /// The x position of the top left corner.
+    pub x: f32,
+    /// The y position of the top left corner.
+    pub y: f32,
+    /// The width and height of the image, uses the size of the image data if not set.
+    pub size: Option<(f32, f32)>,
+    /// The z-index of the image.
+    pub z_index: isize,
+}
+
+impl Image {
+    /// Loads the image at the given path.
+    ///
+    /// The format is picked from the file extension, `png`, `jpg`, `jpeg` and `gif` are supported.
+    pub fn open(
+        path: impl AsRef<std::path::Path>,
+    ) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        let mime = match extension.as_deref() {
+            Some("png") => "image/png",
+            Some("jpg" | "jpeg") => "image/jpeg",
+            Some("gif") => "image/gif",
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "unsupported image format: {}",
+                        path.display()
+                    ),
+                ))
+            }
+        };
+
+        Ok(Self::from_bytes(&std::fs::read(path)?, mime))
+    }
+
+    /// Creates an image from already loaded data with the given mime type, like `image/png`.
+    pub fn from_bytes(data: &[u8], mime: &str) -> Self {
+        Self {
+            href: format!("data:{};base64,{}", mime, base64(data)),
+            x: 0.0,
+            y: 0.0,
+            size: None,
+            z_index: 0,
+        }
+    }
+
+    /// Moves the top left corner of the image to the given position.
+    pub fn at(mut self, x: f32, y: f32) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    /// Stretches the image to the given size.
+    pub fn size(mut self, width: f32, height: f32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    /// Sets the z-index of the image.
+    pub fn z_index(mut self, z_index: isize) -> Self {
+        self.z_index = z_index;
+        self
+    }
+}
+
+/// Encode the data as standard base64 with padding.
+fn base64(data: &[u8]) -> String {
+    /// The base64 alphabet.
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded =
+        String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let bits =
+            u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let sextet = (bits >> (18 - 6 * index)) & 0b11_1111;
+                encoded.push(ALPHABET[sextet as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+impl Object for Image {
+    fn render(&self) -> (isize, Box<dyn svg::Node>) {
+        let mut image = svg::node::element::Image::new()
+            .set("x", self.x)
+            .set("y", self.y)
+            .set("href", self.href.as_str());
+        if let Some((width, height)) = self.size {
+            image = image.set("width", width).set("height", height);
+        }
+
+        (self.z_index, Box::new(image))
+    }
+}
+
 /// A raw SVG object.
 pub struct RawSvg(String);
 
@@ -279,35 +2166,72 @@ pub struct Math {
     /// The math expression to render.
     pub text: String,
     /// The color of the math expression.
+    ///
+    /// This applies to every glyph, including the content of `\text{}` blocks,
+    /// unless `text_color` is set.
     pub color: Color,
+    /// The color of the `\text{}` parts of the math expression.
+    ///
+    /// Falls back to `color` when not set.
+    pub text_color: Option<Color>,
     /// The size of the math expression.
     pub size: f32,
-    /// The x position of the math expression.
+    /// The x position of the anchor of the math expression.
     pub x: f32,
-    /// The y position of the math expression.
+    /// The y position of the anchor of the math expression.
     pub y: f32,
+    /// Which point of the math expression is placed at `x` and `y`.
+    pub anchor: MathAnchor,
     /// The z-index of the math expression.
     pub z_index: isize,
 }
 
+/// Which point of a `Math` object is placed at its position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MathAnchor {
+    /// The top left corner, set with `Math::at`.
+    TopLeft,
+    /// The start of the baseline, set with `Math::baseline_at`.
+    ///
+    /// Expressions sharing a prefix keep it in the same place, whatever their height.
+    Baseline,
+    /// The center of the bounding box, set with `Math::center_on`.
+    Center,
+}
+
 impl Math {
     /// Creates a new math object.
     pub fn new(text: impl Into<String>) -> Self {
         Self {
             text: text.into(),
             color: Color::rgb(255, 255, 255),
+            text_color: None,
             size: 10.0,
             x: 0.0,
             y: 0.0,
+            anchor: MathAnchor::Center,
             z_index: 0,
         }
-        .center_on(0.0, 0.0)
     }
 
-    /// Sets the position of the math expression.
+    /// Sets the position of the top left corner of the math expression.
+    ///
+    /// This replaces any earlier `center_on` or `baseline_at`.
     pub fn at(mut self, x: f32, y: f32) -> Self {
         self.x = x;
         self.y = y;
+        self.anchor = MathAnchor::TopLeft;
+        self
+    }
+
+    /// Sets the position of the start of the baseline of the math expression.
+    ///
+    /// Unlike `at` this doesnt depend on how tall the expression is,
+    /// so use it to line up expressions that are transformed into each other.
+    pub fn baseline_at(mut self, x: f32, y: f32) -> Self {
+        self.x = x;
+        self.y = y;
+        self.anchor = MathAnchor::Baseline;
         self
     }
 
@@ -323,6 +2247,12 @@ impl Math {
         self
     }
 
+    /// Sets the color of the `\text{}` parts of the math expression.
+    pub fn text_color(mut self, color: Color) -> Self {
+        self.text_color = Some(color);
+        self
+    }
+
     /// Sets the z-index of the math expression.
     pub fn z_index(mut self, z_index: isize) -> Self {
         self.z_index = z_index;
@@ -330,32 +2260,420 @@ impl Math {
     }
 
     /// Centers the math expression on a point.
+    ///
+    /// The expression is measured when it is rendered,
+    /// so it stays centered no matter when `size` is set.
     pub fn center_on(mut self, x: f32, y: f32) -> Self {
-        let bounding_box = self.bounding_box();
-        self.x = x - bounding_box.width() / 2.0;
-        self.y = y - bounding_box.height() / 2.0;
+        self.x = x;
+        self.y = y;
+        self.anchor = MathAnchor::Center;
         self
     }
+
+    /// The position of the top left corner of the expression when rendered.
+    fn origin(&self) -> (f32, f32) {
+        let (x, y) = (self.x, self.y);
+        match self.anchor {
+            MathAnchor::TopLeft => return (x, y),
+            MathAnchor::Baseline => {
+                let (baseline_x, baseline_y) =
+                    math_layout(&self.text).baseline;
+                return (
+                    x - baseline_x * self.size,
+                    y - baseline_y * self.size,
+                );
+            }
+            MathAnchor::Center => {}
+        }
+
+        /// The bounds of every expression measured so far at size 1, keyed by the expression.
+        static BOUNDS: std::sync::OnceLock<
+            std::sync::Mutex<
+                std::collections::HashMap<String, resvg::usvg::Rect>,
+            >,
+        > = std::sync::OnceLock::new();
+        let bounds = BOUNDS.get_or_init(Default::default);
+
+        let cached = bounds.lock().unwrap().get(&self.text).copied();
+        let unscaled = cached.unwrap_or_else(|| {
+            let unscaled = Self {
+                size: 1.0,
+                ..self.clone().at(0.0, 0.0)
+            }
+            .bounding_box();
+            bounds
+                .lock()
+                .unwrap()
+                .insert(self.text.clone(), unscaled);
+            unscaled
+        });
+
+        // The expression scales around its origin, so the offset to the center scales with it.
+        (
+            x - (unscaled.x() + unscaled.width() / 2.0) * self.size,
+            y - (unscaled.y() + unscaled.height() / 2.0) * self.size,
+        )
+    }
+}
+
+/// A single glyph (or rule, like a fraction bar) of a rendered `Math` object.
+#[derive(Clone)]
+pub(crate) struct Glyph {
+    /// What the glyph shows, the outline of glyphs and `rect` for rules.
+    ///
+    /// Glyphs with the same key look the same, apart from size.
+    pub key: String,
+    /// The SVG path data of the glyph, before `transform` is applied.
+    pub data: String,
+    /// The transform placing the glyph in the scene.
+    pub transform: resvg::usvg::Transform,
+    /// The fill color of the glyph.
+    pub color: Color,
+    /// The bounding box of the glyph in the scene.
+    pub bounds: resvg::usvg::Rect,
+}
+
+impl Math {
+    /// Split the rendered math expression into its glyphs, in reading order.
+    pub(crate) fn glyphs(&self) -> Vec<Glyph> {
+        /// Collect every filled path of the group as a glyph.
+        fn collect(
+            group: &resvg::usvg::Group,
+            glyphs: &mut Vec<Glyph>,
+        ) {
+            use resvg::usvg::{Node, Paint};
+
+            for node in group.children() {
+                match node {
+                    Node::Group(group) => collect(group, glyphs),
+                    Node::Path(path) => {
+                        let color = match path.fill().map(|fill| {
+                            (fill.paint(), fill.opacity())
+                        }) {
+                            Some((Paint::Color(color), opacity)) => {
+                                Color(
+                                    color.red,
+                                    color.green,
+                                    color.blue,
+                                    (opacity.get() * 255.0).round()
+                                        as u8,
+                                )
+                            }
+                            _ => continue,
+                        };
+
+                        let data = crate::path_data(path.data());
+                        glyphs.push(Glyph {
+                            key: if is_rule(path.data()) {
+                                "rect".to_string()
+                            } else {
+                                data.clone()
+                            },
+                            data,
+                            transform: path.abs_transform(),
+                            color,
+                            bounds: path.abs_bounding_box(),
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        /// Whether the path is an axis aligned rectangle, which is how mathjax draws rules.
+        fn is_rule(path: &resvg::usvg::tiny_skia_path::Path) -> bool {
+            use resvg::usvg::tiny_skia_path::PathSegment::{
+                Close, LineTo, MoveTo,
+            };
+
+            let segments = path.segments().collect::<Vec<_>>();
+            let corners = match segments[..] {
+                [MoveTo(a), LineTo(b), LineTo(c), LineTo(d), Close] => {
+                    [a, b, c, d]
+                }
+                _ => return false,
+            };
+            (0..4).all(|index| {
+                let from = corners[index];
+                let to = corners[(index + 1) % 4];
+                from.x == to.x || from.y == to.y
+            })
+        }
+
+        let (_, node) = self.render();
+        let doc = svg::Document::new().add(node).to_string();
+        let mut glyphs = Vec::new();
+        if let Ok(tree) = crate::convert_to_resvg(
+            doc,
+            crate::system_font_database(),
+        ) {
+            collect(tree.root(), &mut glyphs);
+        }
+        glyphs
+    }
+
+    /// The amount of glyphs in the rendered expression, including rules like fraction bars.
+    pub fn glyph_count(&self) -> usize {
+        self.glyphs().len()
+    }
+
+    /// The bounding box around a run of glyphs, like a single term of the expression.
+    ///
+    /// Glyphs are numbered in reading order, including rules like fraction bars,
+    /// so `0..3` is the box around the first three glyphs.
+    /// Returns `None` if the range contains no glyphs.
+    pub fn part_bounding_box(
+        &self,
+        glyphs: std::ops::Range<usize>,
+    ) -> Option<resvg::usvg::Rect> {
+        let all = self.glyphs();
+        let part =
+            all.get(glyphs.start..glyphs.end.min(all.len()))?;
+        part.iter().map(|glyph| glyph.bounds).reduce(|a, b| {
+            resvg::usvg::Rect::from_ltrb(
+                a.left().min(b.left()),
+                a.top().min(b.top()),
+                a.right().max(b.right()),
+                a.bottom().max(b.bottom()),
+            )
+            .unwrap()
+        })
+    }
+}
+
+/// The mathjax renderer shared by every `Math` object.
+///
+/// Creating one unpacks the mathjax library into a temporary directory,
+/// so it is only done once per process.
+/// That directory is not removed when the process exits.
+fn mathjax_renderer() -> &'static mathjax::MathJax {
+    /// The shared renderer.
+    static RENDERER: std::sync::OnceLock<mathjax::MathJax> =
+        std::sync::OnceLock::new();
+
+    RENDERER.get_or_init(|| mathjax::MathJax::new().unwrap())
+}
+
+/// A math expression laid out by mathjax, before any color is applied.
+#[derive(Clone)]
+struct MathLayout {
+    /// The SVG mathjax rendered.
+    svg: String,
+    /// The start of the baseline, relative to the top left corner at a size of 1.0.
+    baseline: (f32, f32),
+}
+
+/// Render the expression with mathjax, before any color is applied.
+///
+/// The layout does not depend on the color,
+/// so it is cached per expression and recoloring a `Math` object does not re-run mathjax.
+fn math_layout(text: &str) -> MathLayout {
+    /// The cached layouts, keyed by the expression.
+    static CACHE: std::sync::OnceLock<
+        std::sync::Mutex<
+            std::collections::HashMap<String, MathLayout>,
+        >,
+    > = std::sync::OnceLock::new();
+    let cache = CACHE.get_or_init(Default::default);
+
+    if let Some(layout) = cache.lock().unwrap().get(text) {
+        return layout.clone();
+    }
+
+    let svg = mathjax_renderer().render(text).unwrap().into_raw();
+    // The baseline is at a y of 0 in the viewBox,
+    // which the root `<svg>` maps into its own size.
+    let baseline = crate::convert_to_resvg(
+        svg.clone(),
+        crate::system_font_database(),
+    )
+    .map_or((0.0, 0.0), |tree| {
+        let view_box = tree.view_box().to_transform(tree.size());
+        (view_box.tx, view_box.ty)
+    });
+
+    let layout = MathLayout { svg, baseline };
+    cache
+        .lock()
+        .unwrap()
+        .insert(text.to_string(), layout.clone());
+    layout
+}
+
+/// Rebuild an element of the mathjax SVG, applying the colors of the `Math` object.
+///
+/// Every glyph (including `\text{}` glyphs) inherits `currentColor` from the root group,
+/// this is the same thing `mathjax::Render::set_color` does.
+/// With a `text_color` the `mtext` groups get their own fill and stroke instead.
+fn math_element(
+    node: resvg::usvg::roxmltree::Node,
+    color: &str,
+    text_color: Option<&str>,
+) -> svg::node::element::Element {
+    /// The namespace of `xlink:href`, which mathjax uses to reuse glyphs.
+    const XLINK: &str = "http://www.w3.org/1999/xlink";
+
+    let mut element =
+        svg::node::element::Element::new(node.tag_name().name());
+    for attribute in node.attributes() {
+        let name = match attribute.namespace() {
+            Some(XLINK) => format!("xlink:{}", attribute.name()),
+            _ => attribute.name().to_string(),
+        };
+        let value = match attribute.value() {
+            "currentColor" => color,
+            value => value,
+        };
+        element.assign(name, value);
+    }
+    if let Some(text_color) = text_color {
+        if node.attribute("data-mml-node") == Some("mtext") {
+            element.assign("fill", text_color);
+            element.assign("stroke", text_color);
+        }
+    }
+
+    for child in node.children() {
+        if child.is_element() {
+            element.append(math_element(child, color, text_color));
+        } else if let Some(text) = child.text() {
+            element.append(svg::node::Text::new(text));
+        }
+    }
+    element
 }
 
 impl Object for Math {
     fn render(&self) -> (isize, Box<dyn svg::Node>) {
-        let renderer = mathjax::MathJax::new().unwrap();
-        let mut result = renderer.render(&self.text).unwrap();
-        result.set_color(self.color.as_css().as_ref());
-        let svg = result.into_raw();
-
-        let transform = format!(
-            "translate({}, {}) scale({})",
-            self.x, self.y, self.size
+        let (x, y) = self.origin();
+        let mut group = svg::node::element::Group::new().set(
+            "transform",
+            format!("translate({}, {}) scale({})", x, y, self.size),
         );
-        let svg = format!(
-            r#"
-            <g transform="{}">{}</g>
-            "#,
-            transform, svg
+
+        let svg = math_layout(&self.text).svg;
+        if let Ok(document) =
+            resvg::usvg::roxmltree::Document::parse(&svg)
+        {
+            let text_color =
+                self.text_color.map(|color| color.as_css());
+            let mut root = math_element(
+                document.root_element(),
+                &self.color.as_css(),
+                text_color.as_deref(),
+            );
+            root.assign("xmlns", "http://www.w3.org/2000/svg");
+            root.assign(
+                "xmlns:xlink",
+                "http://www.w3.org/1999/xlink",
+            );
+            group = group.add(root);
+        }
+
+        (self.z_index, Box::new(group))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn math_glyphs_are_placed_in_reading_order() {
+        let math = Math::new("a+b").at(0.0, 0.0);
+        let glyphs = math.glyphs();
+        assert_eq!(glyphs.len(), 3);
+
+        // Each glyph ends before the next one starts.
+        for pair in glyphs.windows(2) {
+            assert!(pair[0].bounds.right() < pair[1].bounds.left());
+        }
+        assert_ne!(glyphs[0].key, glyphs[1].key);
+        assert_ne!(glyphs[0].key, glyphs[2].key);
+
+        // Moving the expression moves every glyph by the same amount.
+        let moved = math.at(100.0, 50.0).glyphs();
+        for (glyph, moved) in glyphs.iter().zip(&moved) {
+            assert_eq!(glyph.key, moved.key);
+            assert!(
+                (moved.bounds.x() - glyph.bounds.x() - 100.0).abs()
+                    < 1e-3
+            );
+            assert!(
+                (moved.bounds.y() - glyph.bounds.y() - 50.0).abs()
+                    < 1e-3
+            );
+        }
+    }
+
+    #[test]
+    fn math_glyphs_share_keys_by_shape() {
+        let glyphs = Math::new(r"\frac{a}{a}").glyphs();
+        let keys = glyphs
+            .iter()
+            .map(|glyph| glyph.key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(keys.len(), 3);
+        assert_eq!(keys[0], keys[1]);
+        assert_eq!(keys[2], "rect");
+    }
+
+    #[test]
+    fn math_text_color_only_applies_to_text() {
+        let white = Color::rgb(255, 255, 255);
+        let red = Color::rgb(255, 0, 0);
+        let glyphs = Math::new(r"\text{hi}+x")
+            .color(white)
+            .text_color(red)
+            .glyphs();
+
+        let colors = glyphs
+            .iter()
+            .map(|glyph| glyph.color.as_css())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            colors,
+            [red, red, white, white].map(|color| color.as_css())
         );
+    }
+
+    #[test]
+    fn convex_hull_drops_inner_and_collinear_points() {
+        let square =
+            vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        let points = [
+            (5.0, 5.0),
+            (10.0, 10.0),
+            (5.0, 0.0),
+            (0.0, 0.0),
+            (0.0, 10.0),
+            (10.0, 5.0),
+            (10.0, 0.0),
+            (0.0, 0.0),
+        ];
+        assert_eq!(Polygon::convex_hull(&points).points, square);
 
-        (self.z_index, Box::new(svg::node::Blob::new(svg)))
+        // The winding doesnt depend on the order the points are given in.
+        let mut reversed = points;
+        reversed.reverse();
+        assert_eq!(Polygon::convex_hull(&reversed).points, square);
+    }
+
+    #[test]
+    fn convex_hull_of_few_points() {
+        assert!(Polygon::convex_hull(&[]).points.is_empty());
+        assert_eq!(
+            Polygon::convex_hull(&[(1.0, 2.0)]).points,
+            [(1.0, 2.0)]
+        );
+        assert_eq!(
+            Polygon::convex_hull(&[
+                (3.0, 0.0),
+                (1.0, 2.0),
+                (3.0, 0.0)
+            ])
+            .points,
+            [(1.0, 2.0), (3.0, 0.0)]
+        );
     }
 }
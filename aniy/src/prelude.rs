@@ -0,0 +1,45 @@
+//! Re-exports the commonly used traits and types,
+//! so a scene can be built with a single `use aniy::prelude::*;`.
+//!
+//! `objects::Path` is exported as `SvgPath`, so it doesnt shadow `std::path::Path`.
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use aniy::prelude::*;
+//!
+//! fn main() -> Result<(), RenderError> {
+//!     let mut renderer = Renderer::new(1920, 1080);
+//!
+//!     let circle =
+//!         Arc::new(Circle::new(100.0).fill(Color::rgb(200, 0, 0)));
+//!     renderer.timeline().add_animation(AnimatedObject {
+//!         object: circle.clone(),
+//!         enter: FadeAnimation::new(circle.as_ref())
+//!             .container()
+//!             .duration(1.0),
+//!         exit: FadeOut::new(circle.as_ref()).container().as_exit(1.0),
+//!     });
+//!
+//!     renderer.render()?;
+//!     Ok(())
+//! }
+//! ```
+
+pub use crate::animations::{
+    stagger, AnimatedObject, Animation, AnimationContainer, CharFade,
+    Countdown, Cursor, DrawBrace, DrawLine, DrawUnderline, Easing,
+    FadeAnimation, FadeGradient, FadeOut, GrowArrow, InvertFlash,
+    MathTransform, MorphText, Move, NoAnimation, Parallel, ParallelZ,
+    PassingFlash, PathMorph, PolygonDraw, PolygonMorph, PolygonTrace,
+    Pulse, Rotate, Scale, Sequence, SvgTyper, TextType, TextWrite,
+};
+pub use crate::objects::{
+    Arrow, Axes, Brace, Circle, Direction, Fill, Group, Image, Line,
+    LineCap, LineJoin, Math, MathAnchor, Object, Path as SvgPath,
+    Polygon, Rectangle, Text,
+};
+pub use crate::{
+    Color, EncoderPreset, Layer, Origin, OutputFormat, RenderError,
+    Renderer, Timeline,
+};
@@ -12,7 +12,7 @@ const GRAY: Color = Color::rgb(100, 100, 100);
 
 const SHAPE_SCALE: f32 = 300.0;
 
-fn main() {
+fn main() -> Result<(), aniy::RenderError> {
     env_logger::init();
 
     let mut app = aniy::Renderer::new(1920, 1080);
@@ -47,12 +47,12 @@ b_{m1} & b_{m2} & \cdots & b_{mn}
             .duration(5.0),
         exit: animations::FadeGradient::new(text.as_ref())
             .container()
-            .reverse()
-            .duration(1.0),
+            .as_exit(1.0),
     }
     .lifetime(1.0);
 
     timeline.add_animation(text_anim);
 
-    app.render();
+    app.render()?;
+    Ok(())
 }